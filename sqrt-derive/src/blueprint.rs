@@ -0,0 +1,104 @@
+//! Implementation of `#[derive(Blueprint)]`
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, Expr, ExprCall, ExprPath, Lit, LitStr, Result};
+
+/// Type of admin badge declared with `#[blueprint(admin_badge = ...)]`
+enum AdminBadge {
+    Internal,
+    External(String),
+    None,
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let mut name = None;
+    let mut instantiate = None;
+    let mut admin_badge = AdminBadge::None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("blueprint"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("instantiate") {
+                instantiate = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("admin_badge") {
+                admin_badge = parse_admin_badge(&meta.value()?.parse::<Expr>()?)?;
+            } else {
+                return Err(meta.error("expected `name`, `instantiate` or `admin_badge`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let ident = &input.ident;
+    let name = name.unwrap_or_else(|| ident.to_string());
+    let instantiate = match instantiate {
+        Some(instantiate) => instantiate,
+        None => {
+            return Err(Error::new(
+                ident.span(),
+                "the instantiation function should be declared with #[blueprint(instantiate = \"...\")]",
+            ))
+        }
+    };
+    let admin_badge = match admin_badge {
+        AdminBadge::Internal => quote! { ::sqrt::blueprint::AdminBadge::Internal },
+        AdminBadge::External(badge) => {
+            quote! { ::sqrt::blueprint::AdminBadge::External(String::from(#badge)) }
+        }
+        AdminBadge::None => quote! { ::sqrt::blueprint::AdminBadge::None },
+    };
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::sqrt::blueprint::Blueprint for #ident #type_generics #where_clause {
+            fn instantiation_name(&self) -> &str {
+                #instantiate
+            }
+
+            fn name(&self) -> &str {
+                #name
+            }
+
+            fn has_admin_badge(&self) -> ::sqrt::blueprint::AdminBadge {
+                #admin_badge
+            }
+        }
+    })
+}
+
+/// Parses `internal`, `none` or `external("badge name")`
+fn parse_admin_badge(expr: &Expr) -> Result<AdminBadge> {
+    match expr {
+        Expr::Path(ExprPath { path, .. }) if path.is_ident("internal") => Ok(AdminBadge::Internal),
+        Expr::Path(ExprPath { path, .. }) if path.is_ident("none") => Ok(AdminBadge::None),
+        Expr::Call(ExprCall { func, args, .. }) if args.len() == 1 => {
+            match (func.as_ref(), args.first()) {
+                (Expr::Path(ExprPath { path, .. }), Some(Expr::Lit(lit)))
+                    if path.is_ident("external") =>
+                {
+                    match &lit.lit {
+                        Lit::Str(badge) => Ok(AdminBadge::External(badge.value())),
+                        _ => Err(Error::new_spanned(
+                            expr,
+                            "expected external(\"badge name\")",
+                        )),
+                    }
+                }
+                _ => Err(Error::new_spanned(
+                    expr,
+                    "expected external(\"badge name\")",
+                )),
+            }
+        }
+        _ => Err(Error::new_spanned(
+            expr,
+            "expected `internal`, `none` or `external(\"badge name\")`",
+        )),
+    }
+}
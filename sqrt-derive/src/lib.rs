@@ -0,0 +1,50 @@
+//! Derive macros for the traits of the SQRT library
+//!
+//! The macros are re-exported by the `sqrt` crate and should be used from there.
+
+mod blueprint;
+mod method;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Derives the `Blueprint` trait for a struct representing a blueprint
+///
+/// The blueprint is described with `#[blueprint(...)]`:
+/// * `instantiate = "..."` - name of the function instantiating the blueprint
+/// * `name = "..."` - name of the blueprint, defaults to the name of the struct
+/// * `admin_badge = ...` - `internal`, `external("badge name")` or `none`, which is the default
+#[proc_macro_derive(Blueprint, attributes(blueprint))]
+pub fn derive_blueprint(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    blueprint::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Derives the `Method` trait for an enum whose variants are the methods of a blueprint
+///
+/// Every variant can be annotated with `#[method(...)]`:
+/// * `name = "..."` - name of the method, defaults to the name of the variant in snake case, where a run of uppercase
+///   letters is one word (`GetNFT` calls `get_nft`)
+/// * `admin` - the method needs the admin badge of the component
+/// * `manifest = "..."` - custom name of the manifest of the method
+///
+/// Fields of the variants are converted into the arguments of the method, in order. Primitive types, `String`,
+/// `Decimal`, `PreciseDecimal` and `Arg` are converted automatically; other arguments are declared with `#[arg(...)]`:
+/// * `fungible_bucket = "resource"` / `fungible_proof = "resource"` - the field is the amount of the resource
+/// * `non_fungible_bucket = "resource"` / `non_fungible_proof = "resource"` - the field is an id or a `Vec` of ids
+/// * `fungible_bucket` / `fungible_proof` - the field is a `(resource, amount)` tuple
+/// * `non_fungible_bucket` / `non_fungible_proof` - the field is a `(resource, ids)` tuple
+/// * `worktop_bucket` - the field is the name of a resource taken from the worktop, for calls made by a transaction
+/// * `account`, `component`, `resource`, `package` - the field is the name of an account, component, resource or
+///   package of the test environment
+/// * `position = n` - the field is the argument at index `n` of the method, the other fields filling the remaining
+///   arguments in order
+#[proc_macro_derive(Method, attributes(method, arg))]
+pub fn derive_method(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    method::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,351 @@
+//! Implementation of `#[derive(Method)]`
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, LitInt, LitStr, Result, Type};
+
+/// Arguments declared with `#[method(...)]` on a variant
+#[derive(Default)]
+struct MethodAttributes {
+    name: Option<String>,
+    admin: bool,
+    manifest: Option<String>,
+}
+
+/// How a field is converted into an `Arg`, as declared with `#[arg(...)]`
+enum ArgKind {
+    Auto,
+    FungibleBucket(Option<String>),
+    NonFungibleBucket(Option<String>),
+    FungibleProof(Option<String>),
+    NonFungibleProof(Option<String>),
+    WorktopBucket,
+    Account,
+    Component,
+    Resource,
+    Package,
+}
+
+/// Arguments declared with `#[arg(...)]` on a field
+struct ArgAttributes {
+    kind: ArgKind,
+    /// Position of the argument in the call, when it differs from the position of the field
+    position: Option<usize>,
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(Error::new(
+                input.ident.span(),
+                "Method can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut name_arms = vec![];
+    let mut args_arms = vec![];
+    let mut admin_arms = vec![];
+    let mut manifest_arms = vec![];
+
+    for variant in &data.variants {
+        let variant_ident = &variant.ident;
+        let attributes = method_attributes(variant)?;
+
+        let bindings: Vec<_> = (0..variant.fields.len())
+            .map(|index| format_ident!("field_{}", index))
+            .collect();
+        let pattern = match &variant.fields {
+            Fields::Unit => quote! { Self::#variant_ident },
+            Fields::Unnamed(_) => quote! { Self::#variant_ident(#(#bindings),*) },
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote! { Self::#variant_ident { #(#names: #bindings),* } }
+            }
+        };
+        let ignored_pattern = match &variant.fields {
+            Fields::Unit => quote! { Self::#variant_ident },
+            Fields::Unnamed(_) => quote! { Self::#variant_ident(..) },
+            Fields::Named(_) => quote! { Self::#variant_ident { .. } },
+        };
+
+        let method_name = attributes
+            .name
+            .unwrap_or_else(|| snake_case(&variant_ident.to_string()));
+        name_arms.push(quote! { #ignored_pattern => #method_name });
+
+        let args = variant_args(&variant.fields, &bindings)?;
+        if args.is_empty() {
+            args_arms.push(quote! { #ignored_pattern => None });
+        } else {
+            args_arms.push(quote! { #pattern => Some(vec![#(#args),*]) });
+        }
+
+        let admin = attributes.admin;
+        admin_arms.push(quote! { #ignored_pattern => #admin });
+
+        let manifest = match attributes.manifest {
+            None => quote! { None },
+            Some(manifest) => quote! { Some(#manifest) },
+        };
+        manifest_arms.push(quote! { #ignored_pattern => #manifest });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::sqrt::method::Method for #ident #type_generics #where_clause {
+            fn name(&self) -> &str {
+                match self {
+                    #(#name_arms,)*
+                }
+            }
+
+            fn args(&self) -> Option<Vec<::sqrt::method::Arg>> {
+                match self {
+                    #(#args_arms,)*
+                }
+            }
+
+            fn needs_admin_badge(&self) -> bool {
+                match self {
+                    #(#admin_arms,)*
+                }
+            }
+
+            fn custom_manifest_name(&self) -> Option<&str> {
+                match self {
+                    #(#manifest_arms,)*
+                }
+            }
+        }
+    })
+}
+
+fn method_attributes(variant: &syn::Variant) -> Result<MethodAttributes> {
+    let mut attributes = MethodAttributes::default();
+    for attr in variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("method"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                attributes.name = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else if meta.path.is_ident("admin") {
+                attributes.admin = true;
+            } else if meta.path.is_ident("manifest") {
+                attributes.manifest = Some(meta.value()?.parse::<LitStr>()?.value());
+            } else {
+                return Err(meta.error("expected `name`, `admin` or `manifest`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(attributes)
+}
+
+/// Returns the arguments of a variant, placing first the fields declaring their position
+fn variant_args(fields: &Fields, bindings: &[syn::Ident]) -> Result<Vec<TokenStream>> {
+    let mut args: Vec<Option<TokenStream>> = vec![None; fields.len()];
+    let mut unplaced = vec![];
+    for (field, binding) in fields.iter().zip(bindings) {
+        let attributes = arg_attributes(field)?;
+        let arg = field_arg(&field.ty, &attributes.kind, binding)?;
+        match attributes.position {
+            None => unplaced.push(arg),
+            Some(position) => match args.get_mut(position) {
+                None => {
+                    return Err(Error::new(
+                        field.span(),
+                        format!(
+                            "position {} is out of the {} arguments",
+                            position,
+                            fields.len()
+                        ),
+                    ))
+                }
+                Some(Some(_)) => {
+                    return Err(Error::new(
+                        field.span(),
+                        format!("two arguments have the position {}", position),
+                    ))
+                }
+                Some(slot) => *slot = Some(arg),
+            },
+        }
+    }
+
+    // Fields without position fill the remaining arguments, in order
+    let mut unplaced = unplaced.into_iter();
+    Ok(args
+        .into_iter()
+        .map(|arg| arg.or_else(|| unplaced.next()).unwrap())
+        .collect())
+}
+
+fn arg_attributes(field: &syn::Field) -> Result<ArgAttributes> {
+    let mut kind = ArgKind::Auto;
+    let mut position = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("arg"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("position") {
+                position = Some(meta.value()?.parse::<LitInt>()?.base10_parse()?);
+                return Ok(());
+            }
+
+            let resource = if meta.input.peek(syn::Token![=]) {
+                Some(meta.value()?.parse::<LitStr>()?.value())
+            } else {
+                None
+            };
+
+            let ident = meta
+                .path
+                .get_ident()
+                .map(|ident| ident.to_string())
+                .unwrap_or_default();
+            kind = match (ident.as_str(), resource) {
+                ("fungible_bucket", resource) => ArgKind::FungibleBucket(resource),
+                ("non_fungible_bucket", resource) => ArgKind::NonFungibleBucket(resource),
+                ("fungible_proof", resource) => ArgKind::FungibleProof(resource),
+                ("non_fungible_proof", resource) => ArgKind::NonFungibleProof(resource),
+                ("worktop_bucket", None) => ArgKind::WorktopBucket,
+                ("account", None) => ArgKind::Account,
+                ("component", None) => ArgKind::Component,
+                ("resource", None) => ArgKind::Resource,
+                ("package", None) => ArgKind::Package,
+                _ => return Err(meta.error("unknown argument kind")),
+            };
+            Ok(())
+        })?;
+    }
+
+    Ok(ArgAttributes { kind, position })
+}
+
+fn field_arg(ty: &Type, kind: &ArgKind, binding: &syn::Ident) -> Result<TokenStream> {
+    let arg = quote! { ::sqrt::method::Arg };
+    let value = match kind {
+        ArgKind::Auto => return auto_arg(ty, binding),
+        ArgKind::FungibleBucket(Some(resource)) => {
+            quote! { #arg::FungibleBucketArg(String::from(#resource), #binding.clone()) }
+        }
+        ArgKind::FungibleBucket(None) => {
+            quote! { #arg::FungibleBucketArg(#binding.0.clone(), #binding.1.clone()) }
+        }
+        ArgKind::FungibleProof(Some(resource)) => {
+            quote! { #arg::FungibleProofArg(String::from(#resource), #binding.clone()) }
+        }
+        ArgKind::FungibleProof(None) => {
+            quote! { #arg::FungibleProofArg(#binding.0.clone(), #binding.1.clone()) }
+        }
+        ArgKind::NonFungibleBucket(Some(resource)) => {
+            let ids = ids(ty, binding);
+            quote! { #arg::NonFungibleBucketArg(String::from(#resource), #ids) }
+        }
+        ArgKind::NonFungibleBucket(None) => {
+            quote! { #arg::NonFungibleBucketArg(#binding.0.clone(), #binding.1.clone()) }
+        }
+        ArgKind::NonFungibleProof(Some(resource)) => {
+            let ids = ids(ty, binding);
+            quote! { #arg::NonFungibleProofArg(String::from(#resource), #ids) }
+        }
+        ArgKind::NonFungibleProof(None) => {
+            quote! { #arg::NonFungibleProofArg(#binding.0.clone(), #binding.1.clone()) }
+        }
+        ArgKind::WorktopBucket => quote! { #arg::WorktopBucketArg(#binding.clone()) },
+        ArgKind::Account => quote! { #arg::AccountAddressArg(#binding.clone()) },
+        ArgKind::Component => quote! { #arg::ComponentAddressArg(#binding.clone()) },
+        ArgKind::Resource => quote! { #arg::ResourceAddressArg(#binding.clone()) },
+        ArgKind::Package => quote! { #arg::PackageAddressArg(#binding.clone()) },
+    };
+
+    Ok(value)
+}
+
+/// Converts a field without `#[arg(...)]` according to its type
+fn auto_arg(ty: &Type, binding: &syn::Ident) -> Result<TokenStream> {
+    let arg = quote! { ::sqrt::method::Arg };
+    let variant = match type_name(ty).as_deref() {
+        Some("bool") => quote! { Bool },
+        Some("i8") => quote! { I8 },
+        Some("i16") => quote! { I16 },
+        Some("i32") => quote! { I32 },
+        Some("i64") => quote! { I64 },
+        Some("i128") => quote! { I128 },
+        Some("u8") => quote! { U8 },
+        Some("u16") => quote! { U16 },
+        Some("u32") => quote! { U32 },
+        Some("u64") => quote! { U64 },
+        Some("u128") => quote! { U128 },
+        Some("String") => quote! { StringArg },
+        Some("Decimal") => quote! { DecimalArg },
+        Some("PreciseDecimal") => quote! { PreciseDecimalArg },
+        Some("Arg") => return Ok(quote! { #binding.clone() }),
+        _ => {
+            return Err(Error::new(
+                ty.span(),
+                "cannot convert this type into an argument, declare it with #[arg(...)]",
+            ))
+        }
+    };
+
+    Ok(quote! { #arg::#variant(#binding.clone()) })
+}
+
+/// Returns the ids of a non fungible argument, which can either be a single id or a `Vec` of ids
+fn ids(ty: &Type, binding: &syn::Ident) -> TokenStream {
+    if type_name(ty).as_deref() == Some("String") {
+        quote! { vec![#binding.clone()] }
+    } else {
+        quote! { #binding.clone() }
+    }
+}
+
+fn type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.to_string()),
+        _ => None,
+    }
+}
+
+/// Converts a name in camel case to snake case, runs of uppercase letters being kept as one word
+fn snake_case(name: &str) -> String {
+    let characters: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (index, character) in characters.iter().enumerate() {
+        if character.is_uppercase() {
+            // A word starts after a lowercase letter, or at the last letter of an uppercase run followed by lowercase
+            let previous = index.checked_sub(1).map(|previous| characters[previous]);
+            let next = characters.get(index + 1);
+            let starts_word = match previous {
+                None => false,
+                Some(previous) if previous.is_uppercase() => {
+                    next.is_some_and(|next| next.is_lowercase())
+                }
+                Some(previous) => previous != '_',
+            };
+            if starts_word {
+                snake.push('_');
+            }
+            snake.extend(character.to_lowercase());
+        } else {
+            snake.push(*character);
+        }
+    }
+
+    snake
+}
@@ -0,0 +1,525 @@
+//! Reads the ABI of a blueprint and generates typed bindings from it
+
+use crate::error::SqrtError;
+use crate::method::Arg;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+/// Type of an argument or of a returned value, as described by the ABI of a blueprint
+#[derive(Debug, Clone, PartialEq)]
+pub enum AbiType {
+    Unit,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    String,
+    Decimal,
+    PreciseDecimal,
+    PackageAddress,
+    ComponentAddress,
+    ResourceAddress,
+    SystemAddress,
+    Hash,
+    Bucket,
+    Proof,
+    Vault,
+    Expression,
+    Blob,
+    NonFungibleLocalId,
+    NonFungibleGlobalId,
+    EcdsaSecp256k1PublicKey,
+    EcdsaSecp256k1Signature,
+    EddsaEd25519PublicKey,
+    EddsaEd25519Signature,
+    Option(Box<AbiType>),
+    Vec(Box<AbiType>),
+    Tuple(Vec<AbiType>),
+    HashMap(Box<AbiType>, Box<AbiType>),
+    /// Struct with its name and the types of its fields
+    Struct(String, Vec<AbiType>),
+    /// Enum with its name and, for every variant, its name and the types of its fields
+    Enum(String, Vec<(String, Vec<AbiType>)>),
+    /// Any other type, with the name given by the ABI
+    Other(String),
+}
+
+/// Function or method of a blueprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionAbi {
+    pub name: String,
+    /// Whether the function is a method, called on a component
+    pub is_method: bool,
+    /// Names and types of the arguments
+    pub inputs: Vec<(String, AbiType)>,
+    pub output: AbiType,
+}
+
+/// Functions and methods of a blueprint
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlueprintAbi {
+    pub name: String,
+    pub functions: Vec<FunctionAbi>,
+}
+
+impl FunctionAbi {
+    /// Panics with a message naming the first argument whose type does not match the signature of the function
+    ///
+    /// # Arguments
+    /// * `args` - arguments the function will be called with
+    pub fn check_args(&self, args: &[Arg]) {
+        self.try_check_args(args)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns an error naming the first argument whose type does not match the signature of the function
+    ///
+    /// # Arguments
+    /// * `args` - arguments the function will be called with
+    pub fn try_check_args(&self, args: &[Arg]) -> Result<(), SqrtError> {
+        if args.len() != self.inputs.len() {
+            return Err(SqrtError::ArgumentMismatch(format!(
+                "{} expects {} arguments, got {}",
+                self.name,
+                self.inputs.len(),
+                args.len()
+            )));
+        }
+
+        for (index, (arg, (_, abi_type))) in args.iter().zip(&self.inputs).enumerate() {
+            if !abi_type.accepts(arg) {
+                return Err(SqrtError::ArgumentMismatch(format!(
+                    "argument {} of {} expected {}, got {}",
+                    index,
+                    self.name,
+                    abi_type,
+                    arg.get_type()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BlueprintAbi {
+    /// Parses the ABI of a blueprint exported as JSON by `resim export-abi`
+    ///
+    /// # Arguments
+    /// * `name` - name of the blueprint
+    /// * `json` - exported ABI
+    pub fn from_json(name: &str, json: &str) -> BlueprintAbi {
+        // resim might print other lines before the ABI
+        let start = json
+            .find('{')
+            .expect("Could not find the ABI in the given output");
+        let value: Value = serde_json::from_str(&json[start..])
+            .unwrap_or_else(|error| panic!("Could not parse the ABI of {}: {}", name, error));
+
+        let functions = value["fns"]
+            .as_array()
+            .expect("The ABI does not contain any function")
+            .iter()
+            .map(|function| FunctionAbi {
+                name: function["ident"].as_str().unwrap_or_default().to_string(),
+                is_method: !function["mutability"].is_null(),
+                inputs: named_fields(&function["input"]["fields"]),
+                output: AbiType::from_json(&function["output"]),
+            })
+            .collect();
+
+        BlueprintAbi {
+            name: name.to_string(),
+            functions,
+        }
+    }
+
+    /// Returns the function or method with the given name
+    ///
+    /// # Arguments
+    /// * `name` - name of the function
+    pub fn function(&self, name: &str) -> Option<&FunctionAbi> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// Returns Rust source declaring the [`Blueprint`](crate::blueprint::Blueprint) and an enum implementing
+    /// [`Method`](crate::method::Method) with one typed variant per method of the blueprint
+    ///
+    /// Buckets and proofs are generated as fungible ones and the blueprint has no admin badge; the generated source
+    /// should be edited if it is not the case.
+    /// # Arguments
+    /// * `instantiation_name` - name of the function instantiating the blueprint
+    pub fn generate_bindings(&self, instantiation_name: &str) -> String {
+        let mut output = format!(
+            "// Generated by sqrt from the ABI of blueprint {}\n\n\
+             #[allow(unused_imports)]\n\
+             use scrypto::prelude::{{Decimal, PreciseDecimal}};\n\
+             use sqrt::blueprint::Blueprint;\n\
+             #[allow(unused_imports)]\n\
+             use sqrt::method::{{Arg, Method}};\n\n\
+             #[derive(Blueprint)]\n\
+             #[blueprint(name = \"{}\", instantiate = \"{}\")]\n\
+             pub struct {}Bp {{}}\n",
+            self.name, self.name, instantiation_name, self.name
+        );
+
+        let methods: Vec<&FunctionAbi> = self
+            .functions
+            .iter()
+            .filter(|function| function.is_method)
+            .collect();
+        if methods.is_empty() {
+            return output;
+        }
+
+        output = format!(
+            "{}\n#[derive(Method)]\npub enum {}Methods {{\n",
+            output, self.name
+        );
+        for method in methods {
+            let signature: Vec<String> = method
+                .inputs
+                .iter()
+                .map(|(name, abi_type)| format!("{}: {}", name, abi_type))
+                .collect();
+            let fields: Vec<String> = method
+                .inputs
+                .iter()
+                .map(|(_, abi_type)| abi_type.binding_field())
+                .collect();
+            let fields = if fields.is_empty() {
+                String::new()
+            } else {
+                format!("({})", fields.join(", "))
+            };
+
+            output = format!(
+                "{}    /// `{}({})`\n    #[method(name = \"{}\")]\n    {}{},\n",
+                output,
+                method.name,
+                signature.join(", "),
+                method.name,
+                pascal_case(&method.name),
+                fields
+            );
+        }
+
+        format!("{}}}\n", output)
+    }
+
+    /// Writes the bindings returned by [`BlueprintAbi::generate_bindings`] to a file
+    ///
+    /// # Arguments
+    /// * `instantiation_name` - name of the function instantiating the blueprint
+    /// * `path` - path of the file to write
+    pub fn write_bindings(&self, instantiation_name: &str, path: &str) {
+        fs::write(path, self.generate_bindings(instantiation_name))
+            .unwrap_or_else(|_| panic!("Could not write bindings to {}", path));
+    }
+}
+
+impl AbiType {
+    fn from_json(value: &Value) -> AbiType {
+        let type_name = value["type"].as_str().unwrap_or_default();
+        match type_name {
+            "Unit" => AbiType::Unit,
+            "Bool" => AbiType::Bool,
+            "I8" => AbiType::I8,
+            "I16" => AbiType::I16,
+            "I32" => AbiType::I32,
+            "I64" => AbiType::I64,
+            "I128" => AbiType::I128,
+            "U8" => AbiType::U8,
+            "U16" => AbiType::U16,
+            "U32" => AbiType::U32,
+            "U64" => AbiType::U64,
+            "U128" => AbiType::U128,
+            "String" => AbiType::String,
+            "Option" => AbiType::Option(Box::new(Self::from_json(&value["some_type"]))),
+            "Vec" | "Array" | "TreeSet" | "HashSet" | "IndexSet" => {
+                AbiType::Vec(Box::new(Self::from_json(&value["element_type"])))
+            }
+            "Tuple" => AbiType::Tuple(
+                value["element_types"]
+                    .as_array()
+                    .map(|elements| elements.iter().map(Self::from_json).collect())
+                    .unwrap_or_default(),
+            ),
+            "HashMap" | "TreeMap" | "IndexMap" => AbiType::HashMap(
+                Box::new(Self::from_json(&value["key_type"])),
+                Box::new(Self::from_json(&value["value_type"])),
+            ),
+            "Struct" => AbiType::Struct(
+                value["name"].as_str().unwrap_or_default().to_string(),
+                named_fields(&value["fields"])
+                    .into_iter()
+                    .map(|(_, abi_type)| abi_type)
+                    .collect(),
+            ),
+            "Enum" => AbiType::Enum(
+                value["name"].as_str().unwrap_or_default().to_string(),
+                value["variants"]
+                    .as_array()
+                    .map(|variants| {
+                        variants
+                            .iter()
+                            .map(|variant| {
+                                (
+                                    variant["name"].as_str().unwrap_or_default().to_string(),
+                                    named_fields(&variant["fields"])
+                                        .into_iter()
+                                        .map(|(_, abi_type)| abi_type)
+                                        .collect(),
+                                )
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+            "Custom" => match value["type_id"].as_u64() {
+                Some(type_id) => Self::from_custom_type_id(type_id),
+                None => Self::from_name(value["name"].as_str().unwrap_or_default()),
+            },
+            _ => Self::from_name(type_name),
+        }
+    }
+
+    /// Returns the type with the given name, used for Scrypto custom types
+    fn from_name(name: &str) -> AbiType {
+        match name {
+            "Decimal" => AbiType::Decimal,
+            "PreciseDecimal" => AbiType::PreciseDecimal,
+            "PackageAddress" => AbiType::PackageAddress,
+            "ComponentAddress" => AbiType::ComponentAddress,
+            "ResourceAddress" => AbiType::ResourceAddress,
+            "SystemAddress" => AbiType::SystemAddress,
+            "Hash" => AbiType::Hash,
+            "Bucket" => AbiType::Bucket,
+            "Proof" => AbiType::Proof,
+            "Vault" => AbiType::Vault,
+            "Expression" => AbiType::Expression,
+            "Blob" => AbiType::Blob,
+            "NonFungibleLocalId" => AbiType::NonFungibleLocalId,
+            "NonFungibleGlobalId" | "NonFungibleAddress" => AbiType::NonFungibleGlobalId,
+            "EcdsaSecp256k1PublicKey" => AbiType::EcdsaSecp256k1PublicKey,
+            "EcdsaSecp256k1Signature" => AbiType::EcdsaSecp256k1Signature,
+            "EddsaEd25519PublicKey" => AbiType::EddsaEd25519PublicKey,
+            "EddsaEd25519Signature" => AbiType::EddsaEd25519Signature,
+            _ => AbiType::Other(name.to_string()),
+        }
+    }
+
+    /// Returns the type with the given Scrypto custom type id
+    fn from_custom_type_id(type_id: u64) -> AbiType {
+        match type_id {
+            0x80 => AbiType::PackageAddress,
+            0x81 => AbiType::ComponentAddress,
+            0x82 => AbiType::ResourceAddress,
+            0x83 => AbiType::SystemAddress,
+            0xa0 => AbiType::Expression,
+            0xa1 => AbiType::Bucket,
+            0xa2 => AbiType::Proof,
+            0xb0 => AbiType::Hash,
+            0xb1 => AbiType::EcdsaSecp256k1PublicKey,
+            0xb2 => AbiType::EcdsaSecp256k1Signature,
+            0xb3 => AbiType::EddsaEd25519PublicKey,
+            0xb4 => AbiType::EddsaEd25519Signature,
+            0xb5 => AbiType::Decimal,
+            0xb6 => AbiType::PreciseDecimal,
+            0xb7 => AbiType::NonFungibleLocalId,
+            _ => AbiType::Other(format!("Custom({:#x})", type_id)),
+        }
+    }
+
+    /// Returns whether the given argument can be passed where this type is expected
+    ///
+    /// Types that the ABI does not describe precisely accept any argument.
+    /// # Arguments
+    /// * `arg` - argument to check
+    pub fn accepts(&self, arg: &Arg) -> bool {
+        match (self, arg) {
+            (AbiType::Other(_), _) => true,
+            (AbiType::Unit, Arg::Unit)
+            | (AbiType::Bool, Arg::Bool(_))
+            | (AbiType::I8, Arg::I8(_))
+            | (AbiType::I16, Arg::I16(_))
+            | (AbiType::I32, Arg::I32(_))
+            | (AbiType::I64, Arg::I64(_))
+            | (AbiType::I128, Arg::I128(_))
+            | (AbiType::U8, Arg::U8(_))
+            | (AbiType::U16, Arg::U16(_))
+            | (AbiType::U32, Arg::U32(_))
+            | (AbiType::U64, Arg::U64(_))
+            | (AbiType::U128, Arg::U128(_))
+            | (AbiType::String, Arg::StringArg(_))
+            | (AbiType::Decimal, Arg::DecimalArg(_))
+            | (AbiType::PreciseDecimal, Arg::PreciseDecimalArg(_))
+            | (AbiType::PackageAddress, Arg::PackageAddressArg(_))
+            | (AbiType::ComponentAddress, Arg::ComponentAddressArg(_))
+            | (AbiType::ComponentAddress, Arg::AccountAddressArg(_))
+            | (AbiType::ResourceAddress, Arg::ResourceAddressArg(_))
+            | (AbiType::SystemAddress, Arg::SystemAddressArg(_))
+            | (AbiType::Hash, Arg::HashArg(_))
+            | (AbiType::Bucket, Arg::FungibleBucketArg(_, _))
+            | (AbiType::Bucket, Arg::NonFungibleBucketArg(_, _))
+            | (AbiType::Bucket, Arg::WorktopBucketArg(_))
+            | (AbiType::Proof, Arg::FungibleProofArg(_, _))
+            | (AbiType::Proof, Arg::NonFungibleProofArg(_, _))
+            | (AbiType::Expression, Arg::Expression(_))
+            | (AbiType::Blob, Arg::Blob(_))
+            | (AbiType::NonFungibleLocalId, Arg::NonFungibleLocalId(_))
+            | (AbiType::NonFungibleGlobalId, Arg::NonFungibleGlobalAddress(_, _))
+            | (AbiType::EcdsaSecp256k1PublicKey, Arg::EcdsaSecp256k1PublicKeyArg(_))
+            | (AbiType::EcdsaSecp256k1Signature, Arg::EcdsaSecp256k1Signature(_))
+            | (AbiType::EddsaEd25519PublicKey, Arg::EddsaEd25519PublicKey(_))
+            | (AbiType::EddsaEd25519Signature, Arg::EddsaEd25519Signature(_)) => true,
+            // Expressions such as ENTIRE_WORKTOP resolve to a list of buckets or proofs
+            (AbiType::Vec(_), Arg::Expression(_)) => true,
+            (AbiType::Vec(inner), Arg::VecArg(elements)) => {
+                elements.iter().all(|element| inner.accepts(element))
+            }
+            (AbiType::Tuple(types), Arg::TupleArg(elements))
+            | (AbiType::Struct(_, types), Arg::TupleArg(elements)) => {
+                types.len() == elements.len()
+                    && types
+                        .iter()
+                        .zip(elements)
+                        .all(|(abi_type, element)| abi_type.accepts(element))
+            }
+            (AbiType::Option(inner), Arg::EnumArg(variant, fields)) => {
+                match (variant, fields.as_slice()) {
+                    (0, []) => true,
+                    (1, [value]) => inner.accepts(value),
+                    _ => false,
+                }
+            }
+            (AbiType::Enum(_, variants), Arg::EnumArg(variant, fields)) => {
+                match variants.get(*variant as usize) {
+                    None => false,
+                    Some((_, types)) => {
+                        types.len() == fields.len()
+                            && types
+                                .iter()
+                                .zip(fields)
+                                .all(|(abi_type, field)| abi_type.accepts(field))
+                    }
+                }
+            }
+            (AbiType::HashMap(key_type, value_type), Arg::HashMapArg(entries)) => entries
+                .iter()
+                .all(|(key, value)| key_type.accepts(key) && value_type.accepts(value)),
+            _ => false,
+        }
+    }
+
+    /// Returns the field of a generated `Method` variant for an argument of this type
+    fn binding_field(&self) -> String {
+        match self {
+            AbiType::Bool => String::from("bool"),
+            AbiType::I8 => String::from("i8"),
+            AbiType::I16 => String::from("i16"),
+            AbiType::I32 => String::from("i32"),
+            AbiType::I64 => String::from("i64"),
+            AbiType::I128 => String::from("i128"),
+            AbiType::U8 => String::from("u8"),
+            AbiType::U16 => String::from("u16"),
+            AbiType::U32 => String::from("u32"),
+            AbiType::U64 => String::from("u64"),
+            AbiType::U128 => String::from("u128"),
+            AbiType::String => String::from("String"),
+            AbiType::Decimal => String::from("Decimal"),
+            AbiType::PreciseDecimal => String::from("PreciseDecimal"),
+            AbiType::Bucket => String::from("#[arg(fungible_bucket)] (String, Decimal)"),
+            AbiType::Proof => String::from("#[arg(fungible_proof)] (String, Decimal)"),
+            AbiType::PackageAddress => String::from("#[arg(package)] String"),
+            AbiType::ComponentAddress => String::from("#[arg(component)] String"),
+            AbiType::ResourceAddress => String::from("#[arg(resource)] String"),
+            _ => String::from("Arg"),
+        }
+    }
+}
+
+impl Display for AbiType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let join = |types: &Vec<AbiType>| {
+            types
+                .iter()
+                .map(|abi_type| abi_type.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
+        match self {
+            AbiType::Unit => write!(f, "()"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::I8 => write!(f, "i8"),
+            AbiType::I16 => write!(f, "i16"),
+            AbiType::I32 => write!(f, "i32"),
+            AbiType::I64 => write!(f, "i64"),
+            AbiType::I128 => write!(f, "i128"),
+            AbiType::U8 => write!(f, "u8"),
+            AbiType::U16 => write!(f, "u16"),
+            AbiType::U32 => write!(f, "u32"),
+            AbiType::U64 => write!(f, "u64"),
+            AbiType::U128 => write!(f, "u128"),
+            AbiType::Option(inner) => write!(f, "Option<{}>", inner),
+            AbiType::Vec(inner) => write!(f, "Vec<{}>", inner),
+            AbiType::Tuple(elements) => write!(f, "({})", join(elements)),
+            AbiType::HashMap(key, value) => write!(f, "HashMap<{}, {}>", key, value),
+            AbiType::Struct(name, _) | AbiType::Enum(name, _) | AbiType::Other(name) => {
+                write!(f, "{}", name)
+            }
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Returns the names and types of the fields of a struct or variant, named after their position when unnamed
+fn named_fields(fields: &Value) -> Vec<(String, AbiType)> {
+    if let Some(named) = fields["named"].as_array() {
+        named
+            .iter()
+            .map(|field| {
+                (
+                    field[0].as_str().unwrap_or_default().to_string(),
+                    AbiType::from_json(&field[1]),
+                )
+            })
+            .collect()
+    } else if let Some(unnamed) = fields["unnamed"].as_array() {
+        unnamed
+            .iter()
+            .enumerate()
+            .map(|(index, field)| (format!("arg_{}", index), AbiType::from_json(field)))
+            .collect()
+    } else {
+        vec![]
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    lazy_static! {
+        static ref WORD_RE: Regex = Regex::new(r"[A-Za-z0-9]+").unwrap();
+    }
+
+    WORD_RE
+        .find_iter(name)
+        .map(|word| {
+            let mut characters = word.as_str().chars();
+            match characters.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().chain(characters).collect(),
+            }
+        })
+        .collect()
+}
@@ -1,10 +1,9 @@
-use crate::utils::{generate_owner_badge, run_command};
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::backend::{AccountInfo, Backend};
+use crate::error::SqrtError;
 use scrypto::prelude::Decimal;
 use std::collections::HashMap;
-use std::process::Command;
 
+#[derive(Clone)]
 pub struct Account {
     address: String,
     private_key: String,
@@ -14,32 +13,16 @@ pub struct Account {
 }
 
 impl Account {
-    pub fn new() -> Account {
-        let account_command = run_command(Command::new("resim").arg("new-account"), false);
-        let badge = generate_owner_badge();
-        Self::from(&account_command.0, badge)
-    }
-
-    pub fn from(string_with_info: &str, badge_address: String) -> Account {
-        lazy_static! {
-            static ref ADDRESS_RE: Regex = Regex::new(r"Account component address: (\w*)").unwrap();
-            static ref PRIVATE_KEY_RE: Regex = Regex::new(r"Private key: (\w*)").unwrap();
-        }
+    pub fn new(backend: &mut dyn Backend) -> Result<Account, SqrtError> {
+        let info = backend.new_account()?;
 
-        let address = &ADDRESS_RE
-            .captures(string_with_info)
-            .expect("Could not find address from given string")[1];
-        let private_key = &PRIVATE_KEY_RE
-            .captures(string_with_info)
-            .expect("Could not find private key from given string")[1];
-
-        Account {
-            address: String::from(address),
-            private_key: String::from(private_key),
-            owner_badge: badge_address,
+        Ok(Account {
+            address: info.address,
+            private_key: info.private_key,
+            owner_badge: info.owner_badge,
             fungibles: HashMap::new(),
             non_fungibles: HashMap::new(),
-        }
+        })
     }
 
     pub fn address(&self) -> &str {
@@ -53,6 +36,15 @@ impl Account {
         &self.private_key
     }
 
+    /// Returns the address, private key and owner badge of the account
+    pub fn info(&self) -> AccountInfo {
+        AccountInfo {
+            address: self.address.clone(),
+            private_key: self.private_key.clone(),
+            owner_badge: self.owner_badge.clone(),
+        }
+    }
+
     pub fn amount_owned(&self, resource: &String) -> Decimal {
         match self.non_fungibles.get(resource) {
             None => {}
@@ -65,6 +57,15 @@ impl Account {
         }
     }
 
+    /// Returns the amount of every resource owned by the account, by address
+    pub fn balances(&self) -> HashMap<String, Decimal> {
+        let mut balances = self.fungibles.clone();
+        for (address, ids) in &self.non_fungibles {
+            balances.insert(address.clone(), Decimal::from(ids.len()));
+        }
+        balances
+    }
+
     pub fn get_non_fungibles_ids(&self, address: &String) -> Option<&Vec<String>> {
         self.non_fungibles.get(address)
     }
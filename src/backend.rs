@@ -0,0 +1,133 @@
+//! Defines the ledgers on which a TestEnvironment runs its transactions
+
+use crate::error::SqrtError;
+use crate::return_value::ReturnValue;
+use scrypto::prelude::{Decimal, Instant};
+use std::any::Any;
+use std::collections::HashMap;
+
+/// Information about a newly created account
+#[derive(Debug, Clone)]
+pub struct AccountInfo {
+    pub address: String,
+    pub private_key: String,
+    pub owner_badge: String,
+}
+
+/// Information about a resource of the ledger
+pub struct ResourceInfo {
+    pub address: String,
+    pub name: Option<String>,
+    pub is_fungible: bool,
+}
+
+/// Amount of a resource owned by a component
+pub struct ResourceBalance {
+    pub address: String,
+    pub amount: Decimal,
+    /// Ids owned when the resource is non fungible
+    pub non_fungible_ids: Vec<String>,
+}
+
+/// Trait to implement for a new ledger backend
+///
+/// Operations on the ledger return [`SqrtError::ResimFailure`] when the backend could not run them.
+pub trait Backend {
+    /// Resets the ledger to its initial state
+    fn reset(&mut self) -> Result<(), SqrtError>;
+
+    /// Creates a new account and returns its information
+    fn new_account(&mut self) -> Result<AccountInfo, SqrtError>;
+
+    /// Creates a new token with fixed supply and returns its address
+    ///
+    /// # Arguments
+    /// * `initial_supply` - initial supply of the token
+    fn new_token_fixed(&mut self, initial_supply: Decimal) -> Result<String, SqrtError>;
+
+    /// Creates a new token with mutable supply and returns its address
+    ///
+    /// # Arguments
+    /// * `minter_badge` - address of the badge that can mint the token
+    fn new_token_mutable(&mut self, minter_badge: &str) -> Result<String, SqrtError>;
+
+    /// Publishes a package and returns its address
+    ///
+    /// # Arguments
+    /// * `path` - path to the Scrypto package
+    /// * `owner_badge` - owner badge of the package
+    fn publish_package(&mut self, path: &str, owner_badge: &str) -> Result<String, SqrtError>;
+
+    /// Runs a transaction manifest and returns the transaction receipt and the errors output
+    ///
+    /// # Arguments
+    /// * `manifest_path` - path of the manifest to run
+    /// * `env_bindings` - values of the environment variables of the manifest
+    /// * `signers` - accounts signing the transaction, or only the default account when empty
+    fn run_manifest(
+        &mut self,
+        manifest_path: &str,
+        env_bindings: &[(String, String)],
+        signers: &[AccountInfo],
+    ) -> Result<(String, String), SqrtError>;
+
+    /// Returns the resources of the ledger
+    fn resources(&mut self) -> Result<Vec<ResourceInfo>, SqrtError>;
+
+    /// Returns the metadata of a given resource
+    ///
+    /// # Arguments
+    /// * `address` - address of the resource
+    fn metadata(&mut self, address: &str) -> Result<HashMap<String, String>, SqrtError>;
+
+    /// Returns the resources owned by a given component
+    ///
+    /// # Arguments
+    /// * `address` - address of the component
+    fn balances(&mut self, address: &str) -> Result<Vec<ResourceBalance>, SqrtError>;
+
+    /// Returns the fields of the state of a given component
+    ///
+    /// # Arguments
+    /// * `address` - address of the component
+    fn component_fields(&mut self, address: &str) -> Result<Vec<ReturnValue>, SqrtError>;
+
+    /// Returns the ABI of a blueprint of a published package, as JSON
+    ///
+    /// # Arguments
+    /// * `package_address` - address of the package
+    /// * `blueprint_name` - name of the blueprint
+    fn export_abi(
+        &mut self,
+        package_address: &str,
+        blueprint_name: &str,
+    ) -> Result<String, SqrtError>;
+
+    /// Sets the account that signs the transactions
+    ///
+    /// # Arguments
+    /// * `address` - address of the account
+    /// * `private_key` - private key of the account
+    /// * `owner_badge` - owner badge of the account
+    fn set_default_account(
+        &mut self,
+        address: &str,
+        private_key: &str,
+        owner_badge: &str,
+    ) -> Result<(), SqrtError>;
+
+    /// Sets the current epoch
+    fn set_current_epoch(&mut self, epoch: u64) -> Result<(), SqrtError>;
+
+    /// Sets the current time
+    fn set_current_time(&mut self, time: Instant) -> Result<(), SqrtError>;
+
+    /// Saves the current state of the ledger and returns it
+    fn snapshot(&mut self) -> Box<dyn Any + Send + Sync>;
+
+    /// Restores the ledger to a state saved by [`Backend::snapshot`]
+    ///
+    /// # Arguments
+    /// * `snapshot` - state returned by [`Backend::snapshot`]
+    fn restore(&mut self, snapshot: &dyn Any);
+}
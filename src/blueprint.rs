@@ -1,7 +1,13 @@
 //! Defines traits to be implemented to declare a new blueprint
 
+/// Derives the [`Blueprint`] trait for a struct, see the documentation of the `sqrt-derive` crate
+pub use sqrt_derive::Blueprint;
+
 /// Trait to implement for a new blueprint
-pub trait Blueprint {
+///
+/// Blueprints are shared by the [`Snapshot`](crate::test_environment::Snapshot)s of an environment, so they should be
+/// `Send` and `Sync`.
+pub trait Blueprint: Send + Sync {
     /// Returns the name of the function to instantiate the blueprint
     fn instantiation_name(&self) -> &str;
 
@@ -10,6 +16,27 @@ pub trait Blueprint {
 
     /// Returns the type of admin badge used by the blueprint
     fn has_admin_badge(&self) -> AdminBadge;
+
+    /// Returns the shape of the value returned by the instantiation function, one [`ReturnedItem`] per element of
+    /// the returned tuple
+    ///
+    /// By default, the instantiation function is expected to return the component followed by its admin badge when
+    /// the blueprint uses an internal admin badge.
+    fn instantiation_return(&self) -> Vec<ReturnedItem> {
+        match self.has_admin_badge() {
+            AdminBadge::Internal => vec![ReturnedItem::Component, ReturnedItem::AdminBadge],
+            _ => vec![ReturnedItem::Component],
+        }
+    }
+
+    /// Returns the shape of the value returned by a given function instantiating the blueprint
+    ///
+    /// Defaults to [`Blueprint::instantiation_return`] for every function.
+    /// # Arguments
+    /// * `_function_name` - name of the function
+    fn function_return(&self, _function_name: &str) -> Vec<ReturnedItem> {
+        self.instantiation_return()
+    }
 }
 
 /// Defines the type of admin badge used by a blueprint
@@ -18,3 +45,17 @@ pub enum AdminBadge {
     External(String),
     None,
 }
+
+/// Defines how an element returned by an instantiation function is registered in the test environment
+pub enum ReturnedItem {
+    /// Instantiated component, registered under the name given at instantiation
+    Component,
+    /// Other component, registered under the name given at instantiation followed by `_` and the given suffix
+    OtherComponent(String),
+    /// Bucket containing the admin badge of the instantiated component
+    AdminBadge,
+    /// Bucket of a new resource, registered under the given name
+    Resource(String),
+    /// Element that is not registered
+    Ignored,
+}
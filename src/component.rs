@@ -1,15 +1,23 @@
+#[derive(Clone)]
 pub struct Component {
     address: String,
     package_path: String,
     admin_badge: Option<String>,
+    blueprint_name: Option<String>,
 }
 
 impl Component {
-    pub fn from(address: &str, package_path: &str, admin_badge: Option<String>) -> Component {
+    pub fn from(
+        address: &str,
+        package_path: &str,
+        admin_badge: Option<String>,
+        blueprint_name: Option<String>,
+    ) -> Component {
         Component {
             address: String::from(address),
             package_path: String::from(package_path),
             admin_badge,
+            blueprint_name,
         }
     }
 
@@ -24,4 +32,8 @@ impl Component {
     pub fn admin_badge(&self) -> &Option<String> {
         &self.admin_badge
     }
+
+    pub fn blueprint_name(&self) -> Option<&str> {
+        self.blueprint_name.as_deref()
+    }
 }
@@ -0,0 +1,70 @@
+//! Defines the state of an instantiated component
+
+use crate::backend::ResourceBalance;
+use crate::return_value::{FromReturnValue, ReturnValue};
+use scrypto::prelude::Decimal;
+
+/// State of a component: the values of its fields and the resources held by its vaults
+pub struct ComponentState {
+    fields: Vec<ReturnValue>,
+    balances: Vec<ResourceBalance>,
+}
+
+impl ComponentState {
+    /// Returns a new ComponentState
+    ///
+    /// # Arguments
+    /// * `fields` - values of the fields of the component
+    /// * `balances` - resources held by the vaults of the component
+    pub fn new(fields: Vec<ReturnValue>, balances: Vec<ResourceBalance>) -> ComponentState {
+        ComponentState { fields, balances }
+    }
+
+    /// Returns the values of the fields of the component, in the order of their declaration in the blueprint
+    pub fn fields(&self) -> &Vec<ReturnValue> {
+        &self.fields
+    }
+
+    /// Returns the value of a given field decoded into a Rust type
+    ///
+    /// # Arguments
+    /// * `index` - position of the field in the declaration of the blueprint
+    pub fn field<T: FromReturnValue>(&self, index: usize) -> T {
+        match self.fields.get(index) {
+            None => panic!(
+                "The component only has {} fields, could not read field {}",
+                self.fields.len(),
+                index
+            ),
+            Some(value) => value.decode(),
+        }
+    }
+
+    /// Returns the resources held by the vaults of the component
+    pub fn balances(&self) -> &Vec<ResourceBalance> {
+        &self.balances
+    }
+
+    /// Returns the amount of a given resource held by the vaults of the component
+    ///
+    /// # Arguments
+    /// * `resource_address` - address of the resource
+    pub fn amount(&self, resource_address: &str) -> Decimal {
+        self.balances
+            .iter()
+            .filter(|balance| balance.address == resource_address)
+            .fold(Decimal::zero(), |total, balance| total + balance.amount)
+    }
+
+    /// Returns the ids of a given non fungible resource held by the vaults of the component
+    ///
+    /// # Arguments
+    /// * `resource_address` - address of the resource
+    pub fn non_fungible_ids(&self, resource_address: &str) -> Vec<String> {
+        self.balances
+            .iter()
+            .filter(|balance| balance.address == resource_address)
+            .flat_map(|balance| balance.non_fungible_ids.clone())
+            .collect()
+    }
+}
@@ -0,0 +1,147 @@
+//! Records the cost units consumed by named calls and compares them to a baseline
+
+use fs2::FileExt;
+use scrypto::prelude::{dec, Decimal};
+use serde_json::{Map, Value};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::{env, fs};
+
+/// What to do when a call consumes more cost units than allowed by the baseline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnRegression {
+    /// Prints a warning and lets the test go on
+    Warn,
+    /// Makes the test panic
+    Fail,
+}
+
+/// Configuration of the cost units tracking of a [`TestEnvironment`](crate::test_environment::TestEnvironment)
+///
+/// The cost units consumed by the calls named with
+/// [`ManifestCall::track_cost`](crate::manifest_call::ManifestCall::track_cost) are recorded in the `rtm/costs.json`
+/// file of their package. When a `rtm/costs_baseline.json` file exists, each recorded cost is compared to the one of
+/// the baseline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostTracking {
+    max_increase: Decimal,
+    on_regression: OnRegression,
+}
+
+impl CostTracking {
+    /// Returns a new CostTracking
+    ///
+    /// # Arguments
+    /// * `max_increase` - maximal increase of the cost of a call compared to the baseline, in percent
+    /// * `on_regression` - what to do when a call costs more than allowed
+    pub fn new(max_increase: Decimal, on_regression: OnRegression) -> CostTracking {
+        CostTracking {
+            max_increase,
+            on_regression,
+        }
+    }
+
+    /// Records the cost of a call and checks it against the baseline
+    ///
+    /// # Arguments
+    /// * `package_path` - path of the package of the call
+    /// * `name` - name of the call
+    /// * `cost_units` - cost units consumed by the call
+    pub(crate) fn record(&self, package_path: &str, name: &str, cost_units: u64) {
+        update_costs(&costs_path(package_path, "costs"), name, cost_units);
+
+        let baseline = match read_costs(&costs_path(package_path, "costs_baseline"))
+            .get(name)
+            .and_then(Value::as_u64)
+        {
+            None => return,
+            Some(baseline) => baseline,
+        };
+        if baseline == 0 || cost_units <= baseline {
+            return;
+        }
+        let increase = Decimal::from(cost_units - baseline) * dec!(100) / Decimal::from(baseline);
+        if increase <= self.max_increase {
+            return;
+        }
+
+        let message = format!(
+            "Cost of {} went from {} to {} cost units (+{}%), more than the allowed {}%",
+            name,
+            baseline,
+            cost_units,
+            (cost_units - baseline) * 100 / baseline,
+            self.max_increase
+        );
+        match self.on_regression {
+            OnRegression::Warn => eprintln!("Warning: {}", message),
+            OnRegression::Fail => panic!("{}", message),
+        }
+    }
+}
+
+impl Default for CostTracking {
+    /// Warns when a call costs more than 10% above its baseline
+    fn default() -> Self {
+        CostTracking::new(dec!(10), OnRegression::Warn)
+    }
+}
+
+fn costs_path(package_path: &str, name: &str) -> String {
+    let current_dir = env::current_dir().expect("Could not find current directory");
+    format!(
+        "{}/{}/rtm/{}.json",
+        current_dir.display(),
+        package_path,
+        name
+    )
+}
+
+fn read_costs(path: &str) -> Map<String, Value> {
+    match fs::read_to_string(path) {
+        Err(_) => Map::new(),
+        Ok(content) => parse_costs(path, &content),
+    }
+}
+
+fn parse_costs(path: &str, content: &str) -> Map<String, Value> {
+    if content.trim().is_empty() {
+        return Map::new();
+    }
+    match serde_json::from_str(content) {
+        Ok(Value::Object(costs)) => costs,
+        _ => panic!("Could not read the costs in {}", path),
+    }
+}
+
+/// Sets the cost of a call in the costs file while holding an exclusive lock on it, so that tests running in parallel
+/// do not overwrite each other's costs
+fn update_costs(path: &str, name: &str, cost_units: u64) {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .unwrap_or_else(|error| panic!("Could not open {}: {}", path, error));
+    file.lock_exclusive()
+        .unwrap_or_else(|error| panic!("Could not lock {}: {}", path, error));
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)
+        .unwrap_or_else(|error| panic!("Could not read {}: {}", path, error));
+    let mut costs = parse_costs(path, &content);
+    costs.insert(name.to_string(), Value::from(cost_units));
+    let output = serde_json::to_string_pretty(&costs).unwrap();
+
+    rewrite(&mut file, &output)
+        .unwrap_or_else(|error| panic!("Could not write {}: {}", path, error));
+    // The lock is released when the file is closed
+}
+
+fn rewrite(file: &mut File, output: &str) -> std::io::Result<()> {
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(output.as_bytes())?;
+    file.sync_all()
+}
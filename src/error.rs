@@ -1,8 +1,11 @@
 //! Handles errors from calls to contract
 
-use crate::error::Error::{AssertFailed, Other};
+use crate::error::Error::{AssertFailed, Failure, Other, Panic, ReceiptMatching, Rejection};
+use crate::receipt::{Level, Receipt, TransactionStatus};
 use lazy_static::lazy_static;
 use regex::Regex;
+use scrypto::prelude::Decimal;
+use std::fmt::{Display, Formatter};
 
 pub enum Error {
     /// States that no error is expected
@@ -11,8 +14,105 @@ pub enum Error {
     AssertFailed(String),
     /// States that another error should happen
     Other(String),
+    /// States that the transaction should be committed with a failure matching a given regex
+    Failure(Regex),
+    /// States that the transaction should be rejected for a reason matching a given regex
+    Rejection(Regex),
+    /// States that a component should panic with a message matching a given regex
+    Panic(Regex),
+    /// States that the receipt of the transaction should satisfy a described predicate
+    ReceiptMatching(String, Box<dyn Fn(&Receipt) -> bool>),
 }
 
+/// Error returned by the `try_*` methods instead of panicking
+#[derive(Debug, Clone)]
+pub enum SqrtError {
+    /// The given name is already used by another entity of the same kind
+    NameConflict(String),
+    /// There is no account with the given name
+    UnknownAccount(String),
+    /// There is no resource with the given name
+    UnknownResource(String),
+    /// There is no package with the given name
+    UnknownPackage(String),
+    /// There is no component with the given name
+    UnknownComponent(String),
+    /// There is no blueprint with the given name in the current package
+    UnknownBlueprint(String),
+    /// No package has been published yet
+    NoCurrentPackage,
+    /// No component has been instantiated yet
+    NoCurrentComponent,
+    /// The current account does not own enough of the given resource, whose owned amount is given
+    NotEnoughTokens(String, Decimal),
+    /// The arguments of a method do not match the ABI of its blueprint
+    ArgumentMismatch(String),
+    /// The values returned by a function do not match what its blueprint describes
+    UnexpectedOutput(String),
+    /// The given resource is not fungible
+    NotFungible(String),
+    /// The ids of a new non fungible resource are missing or not all of the same type
+    InvalidIds(String),
+    /// The backend, resim by default, could not run a command
+    ResimFailure { stdout: String, stderr: String },
+    /// A manifest could not be parsed
+    ParseError(String),
+    /// The transaction was rejected or committed with a failure
+    TransactionFailed(Box<Receipt>),
+}
+
+impl Display for SqrtError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqrtError::NameConflict(name) => write!(f, "The name {} is already used!", name),
+            SqrtError::UnknownAccount(name) => write!(f, "The account {} does not exist", name),
+            SqrtError::UnknownResource(name) => write!(f, "The resource {} does not exist!", name),
+            SqrtError::UnknownPackage(name) => write!(f, "The package {} does not exist!", name),
+            SqrtError::UnknownComponent(name) => {
+                write!(f, "The component {} does not exist!", name)
+            }
+            SqrtError::UnknownBlueprint(name) => {
+                write!(
+                    f,
+                    "The blueprint {} does not exist in the current package!",
+                    name
+                )
+            }
+            SqrtError::NoCurrentPackage => write!(f, "Please publish a package!"),
+            SqrtError::NoCurrentComponent => write!(f, "Please instantiate a component!"),
+            SqrtError::NotEnoughTokens(resource, owned) => write!(
+                f,
+                "Current account does not own enough token {} (owns {})",
+                resource, owned
+            ),
+            SqrtError::ArgumentMismatch(error) => write!(f, "{}", error),
+            SqrtError::UnexpectedOutput(error) => write!(f, "{}", error),
+            SqrtError::NotFungible(name) => {
+                write!(
+                    f,
+                    "Cannot mint tokens of the non fungible resource {}",
+                    name
+                )
+            }
+            SqrtError::InvalidIds(error) => write!(f, "{}", error),
+            SqrtError::ResimFailure { stdout, stderr } => write!(
+                f,
+                "There was an error when trying to run resim:\nstdout:\n{}\nstderr:\n{}",
+                stdout, stderr
+            ),
+            SqrtError::ParseError(error) => write!(f, "Could not parse manifest: {}", error),
+            SqrtError::TransactionFailed(receipt) => write!(
+                f,
+                "Manifest failed!\n\
+                    Transaction Output: \n\n{}",
+                receipt.stdout
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SqrtError {}
+
 impl Error {
     /// Checks that the error has happened
     ///
@@ -75,6 +175,60 @@ impl Error {
                     );
                 }
             }
+            Failure(expected_error) => {
+                let receipt = Receipt::from_stdout(&stdout);
+                let matches = match &receipt.status {
+                    TransactionStatus::Failure(error) => expected_error.is_match(error),
+                    _ => false,
+                };
+                Self::expect(
+                    matches,
+                    "committed failure",
+                    expected_error.as_str(),
+                    &stdout,
+                );
+            }
+            Rejection(expected_reason) => {
+                let receipt = Receipt::from_stdout(&stdout);
+                let matches = match &receipt.status {
+                    TransactionStatus::Rejection(reason) => expected_reason.is_match(reason),
+                    _ => false,
+                };
+                Self::expect(matches, "rejection", expected_reason.as_str(), &stdout);
+            }
+            Panic(expected_message) => {
+                let receipt = Receipt::from_stdout(&stdout);
+                let matches = matches!(receipt.status, TransactionStatus::Failure(_))
+                    && receipt.logs.iter().any(|log| {
+                        log.level == Level::Error
+                            && log
+                                .message
+                                .strip_prefix("Panicked at '")
+                                .is_some_and(|message| expected_message.is_match(message))
+                    });
+                Self::expect(matches, "panic", expected_message.as_str(), &stdout);
+            }
+            ReceiptMatching(description, predicate) => {
+                let receipt = Receipt::from_stdout(&stdout);
+                Self::expect(predicate(&receipt), "receipt", description, &stdout);
+            }
+        }
+    }
+
+    /// Compiles the regex of an expected error once, when the error is constructed
+    fn regex(pattern: &str) -> Regex {
+        Regex::new(pattern)
+            .unwrap_or_else(|error| panic!("Invalid expected error {}: {}", pattern, error))
+    }
+
+    fn expect(matches: bool, kind: &str, expected: &str, stdout: &str) {
+        if !matches {
+            panic!(
+                "Manifest did not fail as expected!\n\
+                        Expected {}: {}\n\
+                        Transaction Output: \n\n{}",
+                kind, expected, stdout
+            );
         }
     }
 
@@ -102,3 +256,70 @@ pub fn assert_fail(error_message: &str) -> Error {
 pub fn other_error(error: &str) -> Error {
     Other(Error::to_regex_str(error))
 }
+
+/// Constructs an error expecting the transaction to be committed with any failure
+pub fn committed_failure() -> Error {
+    Failure(Error::regex(""))
+}
+
+/// Constructs an error expecting the transaction to be rejected for any reason
+pub fn rejected() -> Error {
+    Rejection(Error::regex(""))
+}
+
+/// Constructs an error expecting the transaction to be rejected for a reason containing the given text
+///
+/// # Arguments
+/// * `reason` - text contained in the reason of the rejection
+pub fn rejected_with(reason: &str) -> Error {
+    Rejection(Error::regex(&regex::escape(reason)))
+}
+
+/// Constructs an error expecting the transaction to fail because it was not authorized
+pub fn auth_failure() -> Error {
+    Failure(Error::regex(r"AuthError\(Unauthorized"))
+}
+
+/// Constructs an error expecting the transaction to fail because the given rule was not satisfied
+///
+/// # Arguments
+/// * `rule` - text of the missing role or rule, as displayed in the error
+pub fn auth_failure_for(rule: &str) -> Error {
+    Failure(Error::regex(&format!(
+        r"AuthError\(Unauthorized.*{}",
+        regex::escape(rule)
+    )))
+}
+
+/// Constructs an error expecting the transaction to fail because a vault or a bucket did not hold enough resources
+pub fn insufficient_balance() -> Error {
+    Failure(Error::regex(r"InsufficientBalance"))
+}
+
+/// Constructs an error expecting a component to panic with a message matching the given regex
+///
+/// # Arguments
+/// * `pattern` - regex the message should match
+pub fn panic_matching(pattern: &str) -> Error {
+    Panic(Error::regex(pattern))
+}
+
+/// Constructs an error expecting a component to panic with a message containing the given text
+///
+/// # Arguments
+/// * `text` - text contained in the message
+pub fn panic_containing(text: &str) -> Error {
+    Panic(Error::regex(&regex::escape(text)))
+}
+
+/// Constructs an error expecting the receipt of the transaction to satisfy the given predicate
+///
+/// # Arguments
+/// * `description` - description of the predicate, displayed when it is not satisfied
+/// * `predicate` - predicate over the [`Receipt`] of the transaction
+pub fn receipt_matching<F>(description: &str, predicate: F) -> Error
+where
+    F: Fn(&Receipt) -> bool + 'static,
+{
+    ReceiptMatching(description.to_string(), Box::new(predicate))
+}
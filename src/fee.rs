@@ -0,0 +1,139 @@
+//! Configures how transactions pay their fees and reads the fees they paid
+
+use crate::return_value::strip_colors;
+use lazy_static::lazy_static;
+use regex::Regex;
+use scrypto::prelude::{dec, Decimal};
+use std::str::FromStr;
+
+/// Entity locking the fee of the transactions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeePayer {
+    /// Account making the call
+    Caller,
+    /// Component with a `lock_fee` (or `lock_contingent_fee`) method, by name
+    Component(String),
+    /// Account of the environment, by name, which also signs the transactions
+    Account(String),
+}
+
+/// Fee locked at the start of the transactions generated by a [`TestEnvironment`](crate::test_environment::TestEnvironment)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeLock {
+    payer: FeePayer,
+    amount: Decimal,
+    contingent: bool,
+}
+
+impl FeeLock {
+    /// Returns a new FeeLock
+    ///
+    /// # Arguments
+    /// * `payer` - entity locking the fee
+    /// * `amount` - amount of XRD to lock
+    pub fn new(payer: FeePayer, amount: Decimal) -> FeeLock {
+        FeeLock {
+            payer,
+            amount,
+            contingent: false,
+        }
+    }
+
+    /// Locks the fee with `lock_contingent_fee`, so that it is only paid if the transaction succeeds
+    pub fn contingent(mut self) -> FeeLock {
+        self.contingent = true;
+        self
+    }
+
+    pub fn payer(&self) -> &FeePayer {
+        &self.payer
+    }
+
+    pub fn amount(&self) -> Decimal {
+        self.amount
+    }
+
+    pub fn is_contingent(&self) -> bool {
+        self.contingent
+    }
+
+    /// Returns the name of the method locking the fee
+    pub fn method_name(&self) -> &str {
+        if self.contingent {
+            "lock_contingent_fee"
+        } else {
+            "lock_fee"
+        }
+    }
+}
+
+impl Default for FeeLock {
+    /// Locks 100 XRD from the caller
+    fn default() -> Self {
+        FeeLock::new(FeePayer::Caller, dec!(100))
+    }
+}
+
+/// Fees paid by a transaction, as displayed in its receipt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeSummary {
+    /// Maximum number of cost units the transaction could consume
+    pub cost_unit_limit: u64,
+    /// Number of cost units consumed by the execution
+    pub cost_units_consumed: u64,
+    /// Price of a cost unit in XRD
+    pub cost_unit_price: Decimal,
+    /// XRD paid for the execution
+    pub execution_cost: Decimal,
+    /// XRD paid as royalties
+    pub royalty_cost: Decimal,
+    /// Total XRD paid by the transaction
+    pub total: Decimal,
+}
+
+impl FeeSummary {
+    /// Parses the fee summary of a transaction receipt
+    ///
+    /// Returns [`None`] if the receipt does not contain one, for instance when the transaction was rejected.
+    /// # Arguments
+    /// * `stdout` - output of the `resim run` command
+    pub fn from_receipt(stdout: &str) -> Option<FeeSummary> {
+        lazy_static! {
+            static ref FEE_RE: Regex = Regex::new(r"Transaction Fee: (.*)").unwrap();
+            static ref COST_UNITS_RE: Regex = Regex::new(r"Cost Units: (.*)").unwrap();
+            static ref EXECUTION_RE: Regex =
+                Regex::new(r"([\d.]+) XRD used for execution").unwrap();
+            static ref ROYALTY_RE: Regex = Regex::new(r"([\d.]+) XRD used for royalty").unwrap();
+            static ref BRIBES_RE: Regex = Regex::new(r"([\d.]+) XRD in bribes").unwrap();
+            static ref LIMIT_RE: Regex = Regex::new(r"(\d+) limit").unwrap();
+            static ref CONSUMED_RE: Regex = Regex::new(r"(\d+) consumed").unwrap();
+            static ref PRICE_RE: Regex = Regex::new(r"([\d.]+) XRD per cost unit").unwrap();
+        }
+
+        let stdout = strip_colors(stdout);
+        let fee = &FEE_RE.captures(&stdout)?[1];
+        let cost_units = &COST_UNITS_RE.captures(&stdout)?[1];
+
+        let decimal = |re: &Regex, line: &str| {
+            re.captures(line)
+                .map(|captures| Decimal::from_str(&captures[1]).unwrap())
+                .unwrap_or_else(Decimal::zero)
+        };
+        let integer = |re: &Regex| {
+            re.captures(cost_units)
+                .map(|captures| captures[1].parse::<u64>().unwrap())
+                .unwrap_or(0)
+        };
+
+        let execution_cost = decimal(&EXECUTION_RE, fee);
+        let royalty_cost = decimal(&ROYALTY_RE, fee);
+        Some(FeeSummary {
+            cost_unit_limit: integer(&LIMIT_RE),
+            cost_units_consumed: integer(&CONSUMED_RE),
+            cost_unit_price: decimal(&PRICE_RE, cost_units),
+            execution_cost,
+            royalty_cost,
+            total: execution_cost + royalty_cost + decimal(&BRIBES_RE, fee),
+        })
+    }
+}
@@ -0,0 +1,341 @@
+//! Ledger backend running the Scrypto engine inside the test process
+
+use crate::backend::{AccountInfo, Backend, ResourceBalance, ResourceInfo};
+use crate::error::SqrtError;
+use crate::return_value::ReturnValue;
+use crate::utils::{bind_variables, read_manifest};
+use radix_engine::model::PersistedSubstate;
+use radix_engine::transaction::{TransactionReceipt, TransactionResult};
+use radix_engine::types::*;
+use radix_engine_stores::memory_db::TypedInMemorySubstateStore;
+use scrypto::prelude::Instant;
+use scrypto_unit::TestRunner;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use transaction::builder::ManifestBuilder;
+use transaction::manifest::compile;
+use transaction::model::TransactionManifest;
+
+/// Backend running the transactions with an in-memory [`TestRunner`], without needing the resim binary
+///
+/// Requires the `in-process` feature.
+pub struct InProcessBackend {
+    runner: TestRunner,
+    encoder: Bech32Encoder,
+    decoder: Bech32Decoder,
+    accounts: HashMap<String, EcdsaSecp256k1PublicKey>,
+    default_account: Option<String>,
+    resources: Vec<ResourceAddress>,
+    non_fungibles: HashSet<ResourceAddress>,
+}
+
+impl InProcessBackend {
+    /// Returns a new InProcessBackend
+    pub fn new() -> InProcessBackend {
+        InProcessBackend {
+            runner: TestRunner::new(false),
+            encoder: Bech32Encoder::for_simulator(),
+            decoder: Bech32Decoder::for_simulator(),
+            accounts: HashMap::new(),
+            default_account: None,
+            resources: vec![RADIX_TOKEN],
+            non_fungibles: HashSet::new(),
+        }
+    }
+
+    fn execute(
+        &mut self,
+        manifest: TransactionManifest,
+        signers: &[AccountInfo],
+    ) -> TransactionReceipt {
+        let signers: Vec<&String> = if signers.is_empty() {
+            self.default_account.iter().collect()
+        } else {
+            signers.iter().map(|signer| &signer.address).collect()
+        };
+        let initial_proofs = signers
+            .into_iter()
+            .map(|account| {
+                NonFungibleGlobalId::from_public_key(self.accounts.get(account).unwrap())
+            })
+            .collect();
+
+        let receipt = self.runner.execute_manifest(manifest, initial_proofs);
+        if let TransactionResult::Commit(commit) = &receipt.result {
+            self.resources
+                .extend(commit.entity_changes.new_resource_addresses.iter().cloned());
+
+            // The type of the new resources is read from their resource manager
+            for output in commit.state_updates.up_substates.values() {
+                if let PersistedSubstate::ResourceManager(manager) = &output.substate {
+                    if matches!(manager.resource_type, ResourceType::NonFungible { .. }) {
+                        self.non_fungibles.insert(manager.resource_address);
+                    }
+                }
+            }
+        }
+
+        receipt
+    }
+
+    fn default_account(&self) -> ComponentAddress {
+        let account = self
+            .default_account
+            .as_ref()
+            .expect("No default account was set");
+        self.component_address(account)
+    }
+
+    fn component_address(&self, address: &str) -> ComponentAddress {
+        self.decoder
+            .validate_and_decode_component_address(address)
+            .expect(&format!("{} is not a valid component address", address))
+    }
+
+    fn resource_address(&self, address: &str) -> ResourceAddress {
+        self.decoder
+            .validate_and_decode_resource_address(address)
+            .expect(&format!("{} is not a valid resource address", address))
+    }
+
+    fn badge_to_string(&self, badge: &NonFungibleGlobalId) -> String {
+        format!(
+            "{}:{}",
+            self.encoder
+                .encode_resource_address_to_string(&badge.resource_address()),
+            badge.local_id()
+        )
+    }
+
+    fn new_resource(&mut self, manifest: TransactionManifest) -> Result<String, SqrtError> {
+        let receipt = self.execute(manifest, &[]);
+        match &receipt.result {
+            TransactionResult::Commit(commit) if commit.outcome.is_success() => {
+                let resource = commit.entity_changes.new_resource_addresses[0];
+                Ok(self.encoder.encode_resource_address_to_string(&resource))
+            }
+            _ => Err(SqrtError::ResimFailure {
+                stdout: receipt.display(&self.encoder).to_string(),
+                stderr: String::new(),
+            }),
+        }
+    }
+}
+
+impl Default for InProcessBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for InProcessBackend {
+    fn reset(&mut self) -> Result<(), SqrtError> {
+        *self = Self::new();
+        Ok(())
+    }
+
+    fn new_account(&mut self) -> Result<AccountInfo, SqrtError> {
+        let (public_key, private_key, address) = self.runner.new_allocated_account();
+        let address = self.encoder.encode_component_address_to_string(&address);
+        let owner_badge = NonFungibleGlobalId::from_public_key(&public_key);
+        self.accounts.insert(address.clone(), public_key);
+        if self.default_account.is_none() {
+            self.default_account = Some(address.clone());
+        }
+
+        Ok(AccountInfo {
+            address,
+            private_key: private_key
+                .to_bytes()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            owner_badge: self.badge_to_string(&owner_badge),
+        })
+    }
+
+    fn new_token_fixed(&mut self, initial_supply: Decimal) -> Result<String, SqrtError> {
+        let manifest = ManifestBuilder::new()
+            .lock_fee(FAUCET_COMPONENT, 100.into())
+            .new_token_fixed(BTreeMap::new(), initial_supply)
+            .call_method(
+                self.default_account(),
+                "deposit_batch",
+                args!(Expression::entire_worktop()),
+            )
+            .build();
+        self.new_resource(manifest)
+    }
+
+    fn new_token_mutable(&mut self, minter_badge: &str) -> Result<String, SqrtError> {
+        let manifest = ManifestBuilder::new()
+            .lock_fee(FAUCET_COMPONENT, 100.into())
+            .new_token_mutable(BTreeMap::new(), self.resource_address(minter_badge))
+            .build();
+        self.new_resource(manifest)
+    }
+
+    fn publish_package(&mut self, path: &str, owner_badge: &str) -> Result<String, SqrtError> {
+        // The owner badge is the virtual badge of one of the accounts, as returned by new_account
+        let badge = self
+            .accounts
+            .values()
+            .map(NonFungibleGlobalId::from_public_key)
+            .find(|badge| self.badge_to_string(badge) == owner_badge)
+            .ok_or_else(|| SqrtError::ResimFailure {
+                stdout: String::new(),
+                stderr: format!("{} is not the owner badge of an account", owner_badge),
+            })?;
+
+        let package_address = self.runner.compile_and_publish_with_owner(path, badge);
+        Ok(self
+            .encoder
+            .encode_package_address_to_string(&package_address))
+    }
+
+    fn run_manifest(
+        &mut self,
+        manifest_path: &str,
+        env_bindings: &[(String, String)],
+        signers: &[AccountInfo],
+    ) -> Result<(String, String), SqrtError> {
+        let manifest = read_manifest(manifest_path)?;
+        let manifest = bind_variables(manifest, env_bindings);
+
+        match compile(&manifest, &NetworkDefinition::simulator(), vec![]) {
+            Err(error) => Err(SqrtError::ResimFailure {
+                stdout: String::new(),
+                stderr: format!("{:?}", error),
+            }),
+            Ok(compiled_manifest) => {
+                // The receipt is rendered as resim does, so that it is parsed in the same way
+                let receipt = self.execute(compiled_manifest, signers);
+                Ok((receipt.display(&self.encoder).to_string(), String::new()))
+            }
+        }
+    }
+
+    fn resources(&mut self) -> Result<Vec<ResourceInfo>, SqrtError> {
+        let mut resources = vec![];
+        for resource in self.resources.clone() {
+            let metadata = self.runner.get_metadata(GlobalAddress::Resource(resource));
+            resources.push(ResourceInfo {
+                address: self.encoder.encode_resource_address_to_string(&resource),
+                name: metadata.get("name").cloned(),
+                is_fungible: !self.non_fungibles.contains(&resource),
+            });
+        }
+
+        Ok(resources)
+    }
+
+    fn metadata(&mut self, address: &str) -> Result<HashMap<String, String>, SqrtError> {
+        let resource = self.resource_address(address);
+        Ok(self
+            .runner
+            .get_metadata(GlobalAddress::Resource(resource))
+            .into_iter()
+            .collect())
+    }
+
+    fn balances(&mut self, address: &str) -> Result<Vec<ResourceBalance>, SqrtError> {
+        let component = self.component_address(address);
+        let mut balances = vec![];
+        for (resource, amount) in self.runner.get_component_resources(component) {
+            let mut non_fungible_ids = vec![];
+            for vault in self.runner.get_component_vaults(component, resource) {
+                if let Some(ids) = self.runner.inspect_nft_vault(vault) {
+                    self.non_fungibles.insert(resource);
+                    non_fungible_ids.extend(ids.iter().map(|id| id.to_string()));
+                }
+            }
+
+            balances.push(ResourceBalance {
+                address: self.encoder.encode_resource_address_to_string(&resource),
+                amount,
+                non_fungible_ids,
+            });
+        }
+
+        Ok(balances)
+    }
+
+    fn component_fields(&mut self, address: &str) -> Result<Vec<ReturnValue>, SqrtError> {
+        let component = self.component_address(address);
+        let state: ScryptoValue = self.runner.get_component_state(component);
+        let context = ScryptoValueDisplayContext::with_optional_bech32(Some(&self.encoder));
+        Ok(
+            match ReturnValue::parse(&state.display(context).to_string()) {
+                ReturnValue::Tuple(fields) => fields,
+                value => vec![value],
+            },
+        )
+    }
+
+    fn export_abi(
+        &mut self,
+        package_address: &str,
+        blueprint_name: &str,
+    ) -> Result<String, SqrtError> {
+        let package_address = self
+            .decoder
+            .validate_and_decode_package_address(package_address)
+            .expect(&format!(
+                "{} is not a valid package address",
+                package_address
+            ));
+        let abi = self.runner.export_abi(package_address, blueprint_name);
+        Ok(serde_json::to_string(&abi).expect("Could not serialize the ABI"))
+    }
+
+    fn set_default_account(
+        &mut self,
+        address: &str,
+        _private_key: &str,
+        _owner_badge: &str,
+    ) -> Result<(), SqrtError> {
+        self.default_account = Some(address.to_string());
+        Ok(())
+    }
+
+    fn set_current_epoch(&mut self, epoch: u64) -> Result<(), SqrtError> {
+        self.runner.set_current_epoch(epoch);
+        Ok(())
+    }
+
+    fn set_current_time(&mut self, time: Instant) -> Result<(), SqrtError> {
+        self.runner
+            .set_current_time(time.seconds_since_unix_epoch * 1000);
+        Ok(())
+    }
+
+    fn snapshot(&mut self) -> Box<dyn Any + Send + Sync> {
+        Box::new(InProcessSnapshot {
+            substate_store: self.runner.substate_store().clone(),
+            accounts: self.accounts.clone(),
+            default_account: self.default_account.clone(),
+            resources: self.resources.clone(),
+            non_fungibles: self.non_fungibles.clone(),
+        })
+    }
+
+    fn restore(&mut self, snapshot: &dyn Any) {
+        let snapshot = snapshot
+            .downcast_ref::<InProcessSnapshot>()
+            .expect("The snapshot was not taken by an InProcessBackend");
+        *self.runner.substate_store_mut() = snapshot.substate_store.clone();
+        self.accounts = snapshot.accounts.clone();
+        self.default_account = snapshot.default_account.clone();
+        self.resources = snapshot.resources.clone();
+        self.non_fungibles = snapshot.non_fungibles.clone();
+    }
+}
+
+/// Copy of the state of an [`InProcessBackend`]
+struct InProcessSnapshot {
+    substate_store: TypedInMemorySubstateStore,
+    accounts: HashMap<String, EcdsaSecp256k1PublicKey>,
+    default_account: Option<String>,
+    resources: Vec<ResourceAddress>,
+    non_fungibles: HashSet<ResourceAddress>,
+}
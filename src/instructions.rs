@@ -1,159 +1,762 @@
+//! Instructions of a transaction manifest
+
+use crate::manifest_parser::Parser;
+use crate::manifest_value::{Content, ManifestValue};
 use std::fmt::{Display, Formatter};
 
+/// Instruction of a transaction manifest
+///
+/// Buckets and proofs created or used by an instruction are identified by their name.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction {
-    CallFunction {
-        package_address_arg: String,
-        blueprint_name_arg: String,
-        function_name_arg: String,
-        args: Vec<String>,
+    TakeFromWorktop {
+        resource_address: ManifestValue,
+        bucket: String,
     },
 
-    CallMethod {
-        component_address_arg: String,
-        method_name: String,
-        args: Vec<String>,
+    TakeFromWorktopByAmount {
+        amount: ManifestValue,
+        resource_address: ManifestValue,
+        bucket: String,
+    },
+
+    TakeFromWorktopByIds {
+        ids: ManifestValue,
+        resource_address: ManifestValue,
+        bucket: String,
+    },
+
+    ReturnToWorktop {
+        bucket: String,
+    },
+
+    AssertWorktopContains {
+        resource_address: ManifestValue,
+    },
+
+    AssertWorktopContainsByAmount {
+        amount: ManifestValue,
+        resource_address: ManifestValue,
+    },
+
+    AssertWorktopContainsByIds {
+        ids: ManifestValue,
+        resource_address: ManifestValue,
+    },
+
+    PopFromAuthZone {
+        proof: String,
+    },
+
+    PushToAuthZone {
+        proof: String,
+    },
+
+    ClearAuthZone,
+
+    CreateProofFromAuthZone {
+        resource_address: ManifestValue,
+        proof: String,
     },
 
     CreateProofFromAuthZoneByAmount {
-        amount_arg: String,
-        resource_address_arg: String,
-        proof_id: u32,
+        amount: ManifestValue,
+        resource_address: ManifestValue,
+        proof: String,
     },
 
     CreateProofFromAuthZoneByIds {
-        ids_arg: String,
-        resource_address_arg: String,
-        proof_id: u32,
+        ids: ManifestValue,
+        resource_address: ManifestValue,
+        proof: String,
+    },
+
+    CreateProofFromBucket {
+        bucket: String,
+        proof: String,
+    },
+
+    CloneProof {
+        proof: String,
+        new_proof: String,
+    },
+
+    DropProof {
+        proof: String,
     },
 
     DropAllProofs,
 
-    TakeFromWorktopByAmount {
-        amount_arg: String,
-        resource_address_arg: String,
-        bucket_id: u32,
+    CallFunction {
+        package_address: ManifestValue,
+        blueprint_name: String,
+        function_name: String,
+        args: Vec<ManifestValue>,
     },
 
-    TakeFromWorktopByIds {
-        ids_arg: String,
-        resource_address_arg: String,
-        bucket_id: u32,
+    CallMethod {
+        component_address: ManifestValue,
+        method_name: String,
+        args: Vec<ManifestValue>,
+    },
+
+    PublishPackage {
+        code: ManifestValue,
+        abi: ManifestValue,
+        royalty_config: ManifestValue,
+        metadata: ManifestValue,
+        access_rules: ManifestValue,
+    },
+
+    PublishPackageWithOwner {
+        code: ManifestValue,
+        abi: ManifestValue,
+        owner_badge: ManifestValue,
+    },
+
+    BurnResource {
+        bucket: String,
+    },
+
+    RecallResource {
+        vault_id: ManifestValue,
+        amount: ManifestValue,
+    },
+
+    SetMetadata {
+        entity_address: ManifestValue,
+        key: ManifestValue,
+        value: ManifestValue,
+    },
+
+    SetPackageRoyaltyConfig {
+        package_address: ManifestValue,
+        royalty_config: ManifestValue,
+    },
+
+    SetComponentRoyaltyConfig {
+        component_address: ManifestValue,
+        royalty_config: ManifestValue,
+    },
+
+    ClaimPackageRoyalty {
+        package_address: ManifestValue,
+    },
+
+    ClaimComponentRoyalty {
+        component_address: ManifestValue,
+    },
+
+    SetMethodAccessRule {
+        entity_address: ManifestValue,
+        index: ManifestValue,
+        key: ManifestValue,
+        rule: ManifestValue,
+    },
+
+    MintFungible {
+        resource_address: ManifestValue,
+        amount: ManifestValue,
+    },
+
+    MintNonFungible {
+        resource_address: ManifestValue,
+        entries: ManifestValue,
+    },
+
+    MintUuidNonFungible {
+        resource_address: ManifestValue,
+        entries: ManifestValue,
+    },
+
+    CreateFungibleResource {
+        divisibility: ManifestValue,
+        metadata: ManifestValue,
+        access_rules: ManifestValue,
+        initial_supply: ManifestValue,
+    },
+
+    CreateFungibleResourceWithOwner {
+        divisibility: ManifestValue,
+        metadata: ManifestValue,
+        owner_badge: ManifestValue,
+        initial_supply: ManifestValue,
+    },
+
+    CreateNonFungibleResource {
+        id_type: ManifestValue,
+        metadata: ManifestValue,
+        access_rules: ManifestValue,
+        initial_supply: ManifestValue,
+    },
+
+    CreateNonFungibleResourceWithOwner {
+        id_type: ManifestValue,
+        metadata: ManifestValue,
+        owner_badge: ManifestValue,
+        initial_supply: ManifestValue,
+    },
+
+    CreateValidator {
+        key: ManifestValue,
+        owner_access_rule: ManifestValue,
+    },
+
+    CreateAccessController {
+        controlled_asset: ManifestValue,
+        primary_role: ManifestValue,
+        recovery_role: ManifestValue,
+        confirmation_role: ManifestValue,
+        timed_recovery_delay_in_minutes: ManifestValue,
+    },
+
+    CreateIdentity {
+        access_rule: ManifestValue,
+    },
+
+    AssertAccessRule {
+        access_rule: ManifestValue,
     },
 }
 
-impl Display for Instruction {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+impl Instruction {
+    /// Parses the instructions of a transaction manifest
+    ///
+    /// # Arguments
+    /// * `manifest` - content of the manifest
+    pub fn parse_manifest(manifest: &str) -> Result<Vec<Instruction>, String> {
+        Parser::new(manifest).parse_instructions()
+    }
+
+    /// Builds an instruction from its name and operands, as read in a manifest
+    ///
+    /// # Arguments
+    /// * `name` - name of the instruction, such as `CALL_METHOD`
+    /// * `operands` - values following the name of the instruction
+    pub fn from_operands(name: &str, operands: Vec<ManifestValue>) -> Result<Instruction, String> {
+        let mut operands = Operands::new(name, operands);
+        let instruction = match name {
+            "TAKE_FROM_WORKTOP" => Instruction::TakeFromWorktop {
+                resource_address: operands.value()?,
+                bucket: operands.named("Bucket")?,
+            },
+            "TAKE_FROM_WORKTOP_BY_AMOUNT" => Instruction::TakeFromWorktopByAmount {
+                amount: operands.value()?,
+                resource_address: operands.value()?,
+                bucket: operands.named("Bucket")?,
+            },
+            "TAKE_FROM_WORKTOP_BY_IDS" => Instruction::TakeFromWorktopByIds {
+                ids: operands.value()?,
+                resource_address: operands.value()?,
+                bucket: operands.named("Bucket")?,
+            },
+            "RETURN_TO_WORKTOP" => Instruction::ReturnToWorktop {
+                bucket: operands.named("Bucket")?,
+            },
+            "ASSERT_WORKTOP_CONTAINS" => Instruction::AssertWorktopContains {
+                resource_address: operands.value()?,
+            },
+            "ASSERT_WORKTOP_CONTAINS_BY_AMOUNT" => Instruction::AssertWorktopContainsByAmount {
+                amount: operands.value()?,
+                resource_address: operands.value()?,
+            },
+            "ASSERT_WORKTOP_CONTAINS_BY_IDS" => Instruction::AssertWorktopContainsByIds {
+                ids: operands.value()?,
+                resource_address: operands.value()?,
+            },
+            "POP_FROM_AUTH_ZONE" => Instruction::PopFromAuthZone {
+                proof: operands.named("Proof")?,
+            },
+            "PUSH_TO_AUTH_ZONE" => Instruction::PushToAuthZone {
+                proof: operands.named("Proof")?,
+            },
+            "CLEAR_AUTH_ZONE" => Instruction::ClearAuthZone,
+            "CREATE_PROOF_FROM_AUTH_ZONE" => Instruction::CreateProofFromAuthZone {
+                resource_address: operands.value()?,
+                proof: operands.named("Proof")?,
+            },
+            "CREATE_PROOF_FROM_AUTH_ZONE_BY_AMOUNT" => {
+                Instruction::CreateProofFromAuthZoneByAmount {
+                    amount: operands.value()?,
+                    resource_address: operands.value()?,
+                    proof: operands.named("Proof")?,
+                }
+            }
+            "CREATE_PROOF_FROM_AUTH_ZONE_BY_IDS" => Instruction::CreateProofFromAuthZoneByIds {
+                ids: operands.value()?,
+                resource_address: operands.value()?,
+                proof: operands.named("Proof")?,
+            },
+            "CREATE_PROOF_FROM_BUCKET" => Instruction::CreateProofFromBucket {
+                bucket: operands.named("Bucket")?,
+                proof: operands.named("Proof")?,
+            },
+            "CLONE_PROOF" => Instruction::CloneProof {
+                proof: operands.named("Proof")?,
+                new_proof: operands.named("Proof")?,
+            },
+            "DROP_PROOF" => Instruction::DropProof {
+                proof: operands.named("Proof")?,
+            },
+            "DROP_ALL_PROOFS" => Instruction::DropAllProofs,
+            "CALL_FUNCTION" => Instruction::CallFunction {
+                package_address: operands.value()?,
+                blueprint_name: operands.string()?,
+                function_name: operands.string()?,
+                args: operands.rest(),
+            },
+            "CALL_METHOD" => Instruction::CallMethod {
+                component_address: operands.value()?,
+                method_name: operands.string()?,
+                args: operands.rest(),
+            },
+            "PUBLISH_PACKAGE" => Instruction::PublishPackage {
+                code: operands.value()?,
+                abi: operands.value()?,
+                royalty_config: operands.value()?,
+                metadata: operands.value()?,
+                access_rules: operands.value()?,
+            },
+            "PUBLISH_PACKAGE_WITH_OWNER" => Instruction::PublishPackageWithOwner {
+                code: operands.value()?,
+                abi: operands.value()?,
+                owner_badge: operands.value()?,
+            },
+            "BURN_RESOURCE" => Instruction::BurnResource {
+                bucket: operands.named("Bucket")?,
+            },
+            "RECALL_RESOURCE" => Instruction::RecallResource {
+                vault_id: operands.value()?,
+                amount: operands.value()?,
+            },
+            "SET_METADATA" => Instruction::SetMetadata {
+                entity_address: operands.value()?,
+                key: operands.value()?,
+                value: operands.value()?,
+            },
+            "SET_PACKAGE_ROYALTY_CONFIG" => Instruction::SetPackageRoyaltyConfig {
+                package_address: operands.value()?,
+                royalty_config: operands.value()?,
+            },
+            "SET_COMPONENT_ROYALTY_CONFIG" => Instruction::SetComponentRoyaltyConfig {
+                component_address: operands.value()?,
+                royalty_config: operands.value()?,
+            },
+            "CLAIM_PACKAGE_ROYALTY" => Instruction::ClaimPackageRoyalty {
+                package_address: operands.value()?,
+            },
+            "CLAIM_COMPONENT_ROYALTY" => Instruction::ClaimComponentRoyalty {
+                component_address: operands.value()?,
+            },
+            "SET_METHOD_ACCESS_RULE" => Instruction::SetMethodAccessRule {
+                entity_address: operands.value()?,
+                index: operands.value()?,
+                key: operands.value()?,
+                rule: operands.value()?,
+            },
+            "MINT_FUNGIBLE" => Instruction::MintFungible {
+                resource_address: operands.value()?,
+                amount: operands.value()?,
+            },
+            "MINT_NON_FUNGIBLE" => Instruction::MintNonFungible {
+                resource_address: operands.value()?,
+                entries: operands.value()?,
+            },
+            "MINT_UUID_NON_FUNGIBLE" => Instruction::MintUuidNonFungible {
+                resource_address: operands.value()?,
+                entries: operands.value()?,
+            },
+            "CREATE_FUNGIBLE_RESOURCE" => Instruction::CreateFungibleResource {
+                divisibility: operands.value()?,
+                metadata: operands.value()?,
+                access_rules: operands.value()?,
+                initial_supply: operands.value()?,
+            },
+            "CREATE_FUNGIBLE_RESOURCE_WITH_OWNER" => Instruction::CreateFungibleResourceWithOwner {
+                divisibility: operands.value()?,
+                metadata: operands.value()?,
+                owner_badge: operands.value()?,
+                initial_supply: operands.value()?,
+            },
+            "CREATE_NON_FUNGIBLE_RESOURCE" => Instruction::CreateNonFungibleResource {
+                id_type: operands.value()?,
+                metadata: operands.value()?,
+                access_rules: operands.value()?,
+                initial_supply: operands.value()?,
+            },
+            "CREATE_NON_FUNGIBLE_RESOURCE_WITH_OWNER" => {
+                Instruction::CreateNonFungibleResourceWithOwner {
+                    id_type: operands.value()?,
+                    metadata: operands.value()?,
+                    owner_badge: operands.value()?,
+                    initial_supply: operands.value()?,
+                }
+            }
+            "CREATE_VALIDATOR" => Instruction::CreateValidator {
+                key: operands.value()?,
+                owner_access_rule: operands.value()?,
+            },
+            "CREATE_ACCESS_CONTROLLER" => Instruction::CreateAccessController {
+                controlled_asset: operands.value()?,
+                primary_role: operands.value()?,
+                recovery_role: operands.value()?,
+                confirmation_role: operands.value()?,
+                timed_recovery_delay_in_minutes: operands.value()?,
+            },
+            "CREATE_IDENTITY" => Instruction::CreateIdentity {
+                access_rule: operands.value()?,
+            },
+            "ASSERT_ACCESS_RULE" => Instruction::AssertAccessRule {
+                access_rule: operands.value()?,
+            },
+            _ => return Err(format!("Unknown instruction {}", name)),
+        };
+        operands.finish()?;
+
+        Ok(instruction)
+    }
+
+    /// Returns the name of the instruction in a manifest
+    pub fn name(&self) -> &str {
         match self {
+            Instruction::TakeFromWorktop { .. } => "TAKE_FROM_WORKTOP",
+            Instruction::TakeFromWorktopByAmount { .. } => "TAKE_FROM_WORKTOP_BY_AMOUNT",
+            Instruction::TakeFromWorktopByIds { .. } => "TAKE_FROM_WORKTOP_BY_IDS",
+            Instruction::ReturnToWorktop { .. } => "RETURN_TO_WORKTOP",
+            Instruction::AssertWorktopContains { .. } => "ASSERT_WORKTOP_CONTAINS",
+            Instruction::AssertWorktopContainsByAmount { .. } => {
+                "ASSERT_WORKTOP_CONTAINS_BY_AMOUNT"
+            }
+            Instruction::AssertWorktopContainsByIds { .. } => "ASSERT_WORKTOP_CONTAINS_BY_IDS",
+            Instruction::PopFromAuthZone { .. } => "POP_FROM_AUTH_ZONE",
+            Instruction::PushToAuthZone { .. } => "PUSH_TO_AUTH_ZONE",
+            Instruction::ClearAuthZone => "CLEAR_AUTH_ZONE",
+            Instruction::CreateProofFromAuthZone { .. } => "CREATE_PROOF_FROM_AUTH_ZONE",
+            Instruction::CreateProofFromAuthZoneByAmount { .. } => {
+                "CREATE_PROOF_FROM_AUTH_ZONE_BY_AMOUNT"
+            }
+            Instruction::CreateProofFromAuthZoneByIds { .. } => {
+                "CREATE_PROOF_FROM_AUTH_ZONE_BY_IDS"
+            }
+            Instruction::CreateProofFromBucket { .. } => "CREATE_PROOF_FROM_BUCKET",
+            Instruction::CloneProof { .. } => "CLONE_PROOF",
+            Instruction::DropProof { .. } => "DROP_PROOF",
+            Instruction::DropAllProofs => "DROP_ALL_PROOFS",
+            Instruction::CallFunction { .. } => "CALL_FUNCTION",
+            Instruction::CallMethod { .. } => "CALL_METHOD",
+            Instruction::PublishPackage { .. } => "PUBLISH_PACKAGE",
+            Instruction::PublishPackageWithOwner { .. } => "PUBLISH_PACKAGE_WITH_OWNER",
+            Instruction::BurnResource { .. } => "BURN_RESOURCE",
+            Instruction::RecallResource { .. } => "RECALL_RESOURCE",
+            Instruction::SetMetadata { .. } => "SET_METADATA",
+            Instruction::SetPackageRoyaltyConfig { .. } => "SET_PACKAGE_ROYALTY_CONFIG",
+            Instruction::SetComponentRoyaltyConfig { .. } => "SET_COMPONENT_ROYALTY_CONFIG",
+            Instruction::ClaimPackageRoyalty { .. } => "CLAIM_PACKAGE_ROYALTY",
+            Instruction::ClaimComponentRoyalty { .. } => "CLAIM_COMPONENT_ROYALTY",
+            Instruction::SetMethodAccessRule { .. } => "SET_METHOD_ACCESS_RULE",
+            Instruction::MintFungible { .. } => "MINT_FUNGIBLE",
+            Instruction::MintNonFungible { .. } => "MINT_NON_FUNGIBLE",
+            Instruction::MintUuidNonFungible { .. } => "MINT_UUID_NON_FUNGIBLE",
+            Instruction::CreateFungibleResource { .. } => "CREATE_FUNGIBLE_RESOURCE",
+            Instruction::CreateFungibleResourceWithOwner { .. } => {
+                "CREATE_FUNGIBLE_RESOURCE_WITH_OWNER"
+            }
+            Instruction::CreateNonFungibleResource { .. } => "CREATE_NON_FUNGIBLE_RESOURCE",
+            Instruction::CreateNonFungibleResourceWithOwner { .. } => {
+                "CREATE_NON_FUNGIBLE_RESOURCE_WITH_OWNER"
+            }
+            Instruction::CreateValidator { .. } => "CREATE_VALIDATOR",
+            Instruction::CreateAccessController { .. } => "CREATE_ACCESS_CONTROLLER",
+            Instruction::CreateIdentity { .. } => "CREATE_IDENTITY",
+            Instruction::AssertAccessRule { .. } => "ASSERT_ACCESS_RULE",
+        }
+    }
+
+    /// Returns the operands of the instruction, in order
+    pub fn operands(&self) -> Vec<ManifestValue> {
+        let string = |value: &String| ManifestValue::String(Content::Literal(value.clone()));
+        match self {
+            Instruction::TakeFromWorktop {
+                resource_address,
+                bucket,
+            } => vec![resource_address.clone(), ManifestValue::bucket(bucket)],
+            Instruction::TakeFromWorktopByAmount {
+                amount,
+                resource_address,
+                bucket,
+            } => vec![
+                amount.clone(),
+                resource_address.clone(),
+                ManifestValue::bucket(bucket),
+            ],
+            Instruction::TakeFromWorktopByIds {
+                ids,
+                resource_address,
+                bucket,
+            } => vec![
+                ids.clone(),
+                resource_address.clone(),
+                ManifestValue::bucket(bucket),
+            ],
+            Instruction::ReturnToWorktop { bucket } => vec![ManifestValue::bucket(bucket)],
+            Instruction::AssertWorktopContains { resource_address } => {
+                vec![resource_address.clone()]
+            }
+            Instruction::AssertWorktopContainsByAmount {
+                amount,
+                resource_address,
+            } => vec![amount.clone(), resource_address.clone()],
+            Instruction::AssertWorktopContainsByIds {
+                ids,
+                resource_address,
+            } => vec![ids.clone(), resource_address.clone()],
+            Instruction::PopFromAuthZone { proof } => vec![ManifestValue::proof(proof)],
+            Instruction::PushToAuthZone { proof } => vec![ManifestValue::proof(proof)],
+            Instruction::ClearAuthZone => vec![],
+            Instruction::CreateProofFromAuthZone {
+                resource_address,
+                proof,
+            } => vec![resource_address.clone(), ManifestValue::proof(proof)],
+            Instruction::CreateProofFromAuthZoneByAmount {
+                amount,
+                resource_address,
+                proof,
+            } => vec![
+                amount.clone(),
+                resource_address.clone(),
+                ManifestValue::proof(proof),
+            ],
+            Instruction::CreateProofFromAuthZoneByIds {
+                ids,
+                resource_address,
+                proof,
+            } => vec![
+                ids.clone(),
+                resource_address.clone(),
+                ManifestValue::proof(proof),
+            ],
+            Instruction::CreateProofFromBucket { bucket, proof } => {
+                vec![ManifestValue::bucket(bucket), ManifestValue::proof(proof)]
+            }
+            Instruction::CloneProof { proof, new_proof } => {
+                vec![ManifestValue::proof(proof), ManifestValue::proof(new_proof)]
+            }
+            Instruction::DropProof { proof } => vec![ManifestValue::proof(proof)],
+            Instruction::DropAllProofs => vec![],
             Instruction::CallFunction {
-                package_address_arg,
-                blueprint_name_arg,
-                function_name_arg,
+                package_address,
+                blueprint_name,
+                function_name,
                 args,
             } => {
-                let mut arg_str = String::new();
-                for arg in args {
-                    arg_str = format!(
-                        "{}\n\
-                         \t{}",
-                        arg_str, arg
-                    );
-                }
-                write!(
-                    f,
-                    "CALL_FUNCTION\n\
-                               \tPackageAddress(\"${{{}}}\")\n\
-                               \t\"{}\"\n\
-                               \t\"{}\"\
-                               {};",
-                    package_address_arg, blueprint_name_arg, function_name_arg, arg_str
-                )
+                let mut operands = vec![
+                    package_address.clone(),
+                    string(blueprint_name),
+                    string(function_name),
+                ];
+                operands.extend(args.iter().cloned());
+                operands
             }
-
             Instruction::CallMethod {
-                component_address_arg,
+                component_address,
                 method_name,
                 args,
             } => {
-                let mut arg_str = String::new();
-                for arg in args {
-                    arg_str = format!(
-                        "{}\n\
-                         \t{}",
-                        arg_str, arg
-                    );
-                }
-                write!(
-                    f,
-                    "CALL_METHOD\n\
-                               \tComponentAddress(\"${{{}}}\")\n\
-                               \t\"{}\"\
-                               {};",
-                    component_address_arg, method_name, arg_str
-                )
+                let mut operands = vec![component_address.clone(), string(method_name)];
+                operands.extend(args.iter().cloned());
+                operands
             }
-            Instruction::CreateProofFromAuthZoneByAmount {
-                amount_arg,
-                resource_address_arg,
-                proof_id,
-            } => {
-                write!(
-                    f,
-                    "CREATE_PROOF_FROM_AUTH_ZONE_BY_AMOUNT\n\
-                              \tDecimal(\"${{{}}}\")
-                              \tResourceAddress(\"${{{}}}\")\n\
-                              \tProof(\"{}\");",
-                    amount_arg, resource_address_arg, proof_id
-                )
+            Instruction::PublishPackage {
+                code,
+                abi,
+                royalty_config,
+                metadata,
+                access_rules,
+            } => vec![
+                code.clone(),
+                abi.clone(),
+                royalty_config.clone(),
+                metadata.clone(),
+                access_rules.clone(),
+            ],
+            Instruction::PublishPackageWithOwner {
+                code,
+                abi,
+                owner_badge,
+            } => vec![code.clone(), abi.clone(), owner_badge.clone()],
+            Instruction::BurnResource { bucket } => vec![ManifestValue::bucket(bucket)],
+            Instruction::RecallResource { vault_id, amount } => {
+                vec![vault_id.clone(), amount.clone()]
             }
-
-            Instruction::CreateProofFromAuthZoneByIds {
-                ids_arg,
-                resource_address_arg,
-                proof_id,
-            } => {
-                write!(
-                    f,
-                    "CREATE_PROOF_FROM_AUTH_ZONE_BY_IDS\n\
-                               \tArray<NonFungibleLocalId>(${{{}}})\n\
-                               \tResourceAddress(\"${{{}}}\")\n\
-                               \tProof(\"{}\");",
-                    ids_arg, resource_address_arg, proof_id
-                )
+            Instruction::SetMetadata {
+                entity_address,
+                key,
+                value,
+            } => vec![entity_address.clone(), key.clone(), value.clone()],
+            Instruction::SetPackageRoyaltyConfig {
+                package_address,
+                royalty_config,
+            } => vec![package_address.clone(), royalty_config.clone()],
+            Instruction::SetComponentRoyaltyConfig {
+                component_address,
+                royalty_config,
+            } => vec![component_address.clone(), royalty_config.clone()],
+            Instruction::ClaimPackageRoyalty { package_address } => vec![package_address.clone()],
+            Instruction::ClaimComponentRoyalty { component_address } => {
+                vec![component_address.clone()]
             }
+            Instruction::SetMethodAccessRule {
+                entity_address,
+                index,
+                key,
+                rule,
+            } => vec![
+                entity_address.clone(),
+                index.clone(),
+                key.clone(),
+                rule.clone(),
+            ],
+            Instruction::MintFungible {
+                resource_address,
+                amount,
+            } => vec![resource_address.clone(), amount.clone()],
+            Instruction::MintNonFungible {
+                resource_address,
+                entries,
+            } => vec![resource_address.clone(), entries.clone()],
+            Instruction::MintUuidNonFungible {
+                resource_address,
+                entries,
+            } => vec![resource_address.clone(), entries.clone()],
+            Instruction::CreateFungibleResource {
+                divisibility,
+                metadata,
+                access_rules,
+                initial_supply,
+            } => vec![
+                divisibility.clone(),
+                metadata.clone(),
+                access_rules.clone(),
+                initial_supply.clone(),
+            ],
+            Instruction::CreateFungibleResourceWithOwner {
+                divisibility,
+                metadata,
+                owner_badge,
+                initial_supply,
+            } => vec![
+                divisibility.clone(),
+                metadata.clone(),
+                owner_badge.clone(),
+                initial_supply.clone(),
+            ],
+            Instruction::CreateNonFungibleResource {
+                id_type,
+                metadata,
+                access_rules,
+                initial_supply,
+            } => vec![
+                id_type.clone(),
+                metadata.clone(),
+                access_rules.clone(),
+                initial_supply.clone(),
+            ],
+            Instruction::CreateNonFungibleResourceWithOwner {
+                id_type,
+                metadata,
+                owner_badge,
+                initial_supply,
+            } => vec![
+                id_type.clone(),
+                metadata.clone(),
+                owner_badge.clone(),
+                initial_supply.clone(),
+            ],
+            Instruction::CreateValidator {
+                key,
+                owner_access_rule,
+            } => vec![key.clone(), owner_access_rule.clone()],
+            Instruction::CreateAccessController {
+                controlled_asset,
+                primary_role,
+                recovery_role,
+                confirmation_role,
+                timed_recovery_delay_in_minutes,
+            } => vec![
+                controlled_asset.clone(),
+                primary_role.clone(),
+                recovery_role.clone(),
+                confirmation_role.clone(),
+                timed_recovery_delay_in_minutes.clone(),
+            ],
+            Instruction::CreateIdentity { access_rule } => vec![access_rule.clone()],
+            Instruction::AssertAccessRule { access_rule } => vec![access_rule.clone()],
+        }
+    }
+}
 
-            Instruction::DropAllProofs => {
-                write!(f, "DROP_ALL_PROOFS;")
-            }
-            Instruction::TakeFromWorktopByAmount {
-                amount_arg,
-                resource_address_arg,
-                bucket_id,
-            } => {
-                write!(
-                    f,
-                    "TAKE_FROM_WORKTOP_BY_AMOUNT\n\
-                               \tDecimal(\"${{{}}}\")\n\
-                               \tResourceAddress(\"${{{}}}\")\n\
-                               \tBucket(\"{}\");",
-                    amount_arg, resource_address_arg, bucket_id
-                )
-            }
+impl Display for Instruction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())?;
+        for operand in self.operands() {
+            write!(f, "\n\t{}", operand)?;
+        }
+        write!(f, ";")
+    }
+}
 
-            Instruction::TakeFromWorktopByIds {
-                resource_address_arg,
-                ids_arg,
-                bucket_id,
-            } => {
-                write!(
-                    f,
-                    "TAKE_FROM_WORKTOP_BY_IDS\n\
-                               \tArray<NonFungibleLocalId>(${{{}}})\n\
-                               \tResourceAddress(\"${{{}}}\")\n\
-                               \tBucket(\"{}\");",
-                    ids_arg, resource_address_arg, bucket_id
-                )
-            }
+/// Operands of an instruction being parsed, consumed in order
+struct Operands {
+    instruction: String,
+    operands: std::vec::IntoIter<ManifestValue>,
+}
+
+impl Operands {
+    fn new(instruction: &str, operands: Vec<ManifestValue>) -> Operands {
+        Operands {
+            instruction: instruction.to_string(),
+            operands: operands.into_iter(),
+        }
+    }
+
+    fn value(&mut self) -> Result<ManifestValue, String> {
+        self.operands
+            .next()
+            .ok_or_else(|| format!("Missing operand for {}", self.instruction))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        match self.value()? {
+            ManifestValue::String(Content::Literal(value)) => Ok(value),
+            other => Err(format!(
+                "{} expected a string, got {}",
+                self.instruction, other
+            )),
+        }
+    }
+
+    /// Returns the name of a bucket or a proof
+    fn named(&mut self, type_name: &str) -> Result<String, String> {
+        match self.value()? {
+            ManifestValue::Custom(name, Content::Literal(value)) if name == type_name => Ok(value),
+            other => Err(format!(
+                "{} expected a {}, got {}",
+                self.instruction, type_name, other
+            )),
+        }
+    }
+
+    fn rest(&mut self) -> Vec<ManifestValue> {
+        self.operands.by_ref().collect()
+    }
+
+    fn finish(&mut self) -> Result<(), String> {
+        match self.operands.next() {
+            None => Ok(()),
+            Some(operand) => Err(format!(
+                "Unexpected operand {} for {}",
+                operand, self.instruction
+            )),
         }
     }
 }
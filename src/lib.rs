@@ -5,24 +5,39 @@
 //! A wide variety of usage examples is available in the [test](tests) directory.
 //!
 //! # TODO for version 1.0
-//! - [ ] Deal with return of blueprint methods
-//! - [ ] Allow multiple arguments return when instantiating a function
-//! - [ ] Allow multiple possible instantiation
-//! - [ ] Deal with blueprints state
-//! - [ ] Deal with returns and automatically check how things should have evolved
-//! - [ ] Automatic implementation of method trait
+//! - [x] Deal with return of blueprint methods
+//! - [ ] Summarise returned buckets as resource and amount, which resim v0.8 does not display
+//! - [x] Allow multiple arguments return when instantiating a function
+//! - [x] Allow multiple possible instantiation
+//! - [x] Deal with blueprints state
+//! - [x] Deal with returns and automatically check how things should have evolved
+//! - [x] Automatic implementation of method trait
 
 extern crate core;
+pub mod abi;
 mod account;
+pub mod backend;
 pub mod blueprint;
 mod component;
+pub mod component_state;
+pub mod cost_tracking;
 pub mod error;
-mod instructions;
-mod manifest;
+pub mod fee;
+#[cfg(feature = "in-process")]
+pub mod in_process;
+pub mod instructions;
+pub mod manifest;
 pub mod manifest_call;
+mod manifest_parser;
+pub mod manifest_value;
 pub mod method;
+pub mod non_fungible;
 pub mod package;
+pub mod receipt;
+pub mod resim;
 mod resource_manager;
+pub mod return_value;
 pub mod test_environment;
+pub mod transaction_builder;
 mod transfer;
 mod utils;
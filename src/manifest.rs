@@ -1,156 +1,424 @@
-use crate::blueprint::Blueprint;
-use crate::instructions::Instruction;
-use crate::method::{Arg, Method};
-use scrypto::prelude::{dec, Decimal};
+//! Builds transaction manifests
 
+use crate::error::SqrtError;
+use crate::fee::FeeLock;
+use crate::instructions::Instruction;
+use crate::manifest_value::{Content, ManifestValue};
+use crate::method::{AddressBook, Arg, Method};
+use crate::non_fungible::{LocalId, NonFungibleData};
+use scrypto::prelude::Decimal;
+
+/// Builder of a transaction manifest
+///
+/// Arguments are written as literal values, only the addresses of the caller, of the called component or package, of
+/// the admin badge and of the fee payer are bound to variables when the manifest is run.
 pub struct Manifest {
     needed_resources: Vec<Instruction>,
     instructions: Vec<Instruction>,
     id: u32,
     has_proofs: bool,
-    arg_count: u32,
+    fee_method: String,
+    fee_amount: Decimal,
 }
 
 impl Manifest {
     pub fn new() -> Self {
+        Self::with_fee_lock(&FeeLock::default())
+    }
+
+    /// Returns a new Manifest locking its fee as described by the given [`FeeLock`]
+    ///
+    /// The payer of the fee is bound to a variable when the manifest is run.
+    /// # Arguments
+    /// * `fee_lock` - how the fee of the transaction is locked
+    pub fn with_fee_lock(fee_lock: &FeeLock) -> Self {
         Self {
             needed_resources: vec![],
             instructions: Vec::new(),
             id: 0,
             has_proofs: false,
-            arg_count: 0,
+            fee_method: fee_lock.method_name().to_string(),
+            fee_amount: fee_lock.amount(),
         }
     }
 
-    pub fn instantiate<B>(&mut self, blueprint: &B, args: &Vec<Arg>)
-    where
-        B: Blueprint + ?Sized,
-    {
-        self.lock_fee(Self::caller_arg(), dec!(100));
-        let args_vec = self.deal_with_args(args);
+    /// Adds an instruction at the end of the manifest
+    ///
+    /// # Arguments
+    /// * `instruction` - instruction to add
+    pub fn add_instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Calls a function of a blueprint of the package bound to the package variable
+    ///
+    /// # Arguments
+    /// * `blueprint_name` - name of the blueprint
+    /// * `function_name` - name of the function
+    /// * `args` - arguments of the function
+    /// * `addresses` - addresses of the entities named by the arguments
+    pub fn call_function(
+        &mut self,
+        blueprint_name: &str,
+        function_name: &str,
+        args: &[Arg],
+        addresses: &dyn AddressBook,
+    ) -> Result<(), SqrtError> {
+        self.lock_bound_fee();
+        let args_vec = self.deal_with_args(args, addresses)?;
 
         let inst = Instruction::CallFunction {
-            package_address_arg: Self::package_arg(),
-            blueprint_name_arg: blueprint.name().to_string(),
-            function_name_arg: blueprint.instantiation_name().to_string(),
+            package_address: ManifestValue::custom_variable("PackageAddress", &Self::package_arg()),
+            blueprint_name: blueprint_name.to_string(),
+            function_name: function_name.to_string(),
             args: args_vec,
         };
 
         self.instructions.push(inst);
         self.drop_proofs();
         self.deposit_batch(Self::caller_arg());
+        Ok(())
     }
 
-    pub fn call_method<M>(&mut self, method: &M)
+    /// Calls a method of the component bound to the component variable
+    ///
+    /// # Arguments
+    /// * `method` - method to call
+    /// * `addresses` - addresses of the entities named by the arguments
+    pub fn call_method<M>(
+        &mut self,
+        method: &M,
+        addresses: &dyn AddressBook,
+    ) -> Result<(), SqrtError>
+    where
+        M: Method,
+    {
+        self.lock_bound_fee();
+        self.add_method_call(
+            method,
+            &Self::component_arg(),
+            &Self::admin_badge_arg(),
+            addresses,
+        )?;
+        self.finish();
+        Ok(())
+    }
+
+    /// Adds a call to a method without locking fees or emptying the worktop, so that several calls can be made in
+    /// the same manifest
+    ///
+    /// # Arguments
+    /// * `method` - method to call
+    /// * `component_arg` - variable bound to the address of the component
+    /// * `admin_badge_arg` - variable bound to the address of the admin badge of the component
+    /// * `addresses` - addresses of the entities named by the arguments
+    pub fn add_method_call<M>(
+        &mut self,
+        method: &M,
+        component_arg: &str,
+        admin_badge_arg: &str,
+        addresses: &dyn AddressBook,
+    ) -> Result<(), SqrtError>
     where
         M: Method,
     {
-        self.lock_fee(Self::caller_arg(), dec!(100));
         if method.needs_admin_badge() {
-            self.create_admin_badge_proof(Self::caller_arg(), Self::admin_badge_arg());
+            self.create_admin_badge_proof(Self::caller_arg(), admin_badge_arg);
         }
 
         let args_vec = match method.args() {
             None => {
                 vec![]
             }
-            Some(args) => self.deal_with_args(&args),
+            Some(args) => self.deal_with_args(&args, addresses)?,
         };
 
         let inst = Instruction::CallMethod {
-            component_address_arg: Self::component_arg(),
+            component_address: ManifestValue::custom_variable("ComponentAddress", component_arg),
             method_name: method.name().to_string(),
             args: args_vec,
         };
 
         self.instructions.push(inst);
+        Ok(())
+    }
+
+    /// Drops the proofs and deposits the content of the worktop in the account of the caller
+    pub fn finish(&mut self) {
         self.drop_proofs();
         self.deposit_batch(Self::caller_arg());
     }
 
     pub fn lock_fee(&mut self, caller_arg: String, amount: Decimal) {
         let inst = Instruction::CallMethod {
-            component_address_arg: caller_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &caller_arg),
             method_name: "lock_fee".to_string(),
-            args: vec![format!("Decimal(\"{}\")", amount)],
+            args: vec![ManifestValue::decimal(amount)],
+        };
+        self.needed_resources.push(inst);
+    }
+
+    /// Creates a non fungible resource with an initial supply deposited in the account of the caller
+    ///
+    /// # Arguments
+    /// * `name` - name of the resource
+    /// * `ids_and_data` - ids of the initial supply with their data
+    /// * `minter_badge` - address of the badge allowed to mint, burn and update the data
+    /// * `addresses` - addresses of the entities named by the data
+    pub fn create_non_fungible_resource(
+        &mut self,
+        name: &str,
+        ids_and_data: &[(LocalId, NonFungibleData)],
+        minter_badge: Option<&str>,
+        addresses: &dyn AddressBook,
+    ) -> Result<(), SqrtError> {
+        let id_type = match ids_and_data.first() {
+            None => {
+                return Err(SqrtError::InvalidIds(String::from(
+                    "A non fungible resource should be created with at least one id",
+                )))
+            }
+            Some((id, _)) => id.id_type(),
+        };
+        if ids_and_data.iter().any(|(id, _)| id.id_type() != id_type) {
+            return Err(SqrtError::InvalidIds(String::from(
+                "All the ids of a non fungible resource should have the same type",
+            )));
+        }
+
+        let mut access_rules = vec![];
+        for key in ["Withdraw", "Deposit"] {
+            access_rules.push(Self::enum_value(
+                &format!("ResourceMethodAuthKey::{}", key),
+                vec![],
+            ));
+            access_rules.push(ManifestValue::Composite(
+                String::from("Tuple"),
+                vec![
+                    Self::enum_value("AccessRule::AllowAll", vec![]),
+                    Self::enum_value("AccessRule::DenyAll", vec![]),
+                ],
+            ));
+        }
+        if let Some(minter_badge) = minter_badge {
+            let badge = ManifestValue::custom("ResourceAddress", minter_badge);
+            let resource =
+                Self::enum_value("SoftResourceOrNonFungible::StaticResource", vec![badge]);
+            let proof_rule = Self::enum_value("ProofRule::Require", vec![resource]);
+            let node = Self::enum_value("AccessRuleNode::ProofRule", vec![proof_rule]);
+            let protected = Self::enum_value("AccessRule::Protected", vec![node]);
+            for key in ["Mint", "Burn", "UpdateNonFungibleData"] {
+                access_rules.push(Self::enum_value(
+                    &format!("ResourceMethodAuthKey::{}", key),
+                    vec![],
+                ));
+                access_rules.push(ManifestValue::Composite(
+                    String::from("Tuple"),
+                    vec![
+                        protected.clone(),
+                        Self::enum_value("AccessRule::DenyAll", vec![]),
+                    ],
+                ));
+            }
+        }
+
+        let mut supply = vec![];
+        for (id, data) in ids_and_data {
+            let immutable = Self::data_values(data.immutable(), addresses)?;
+            let mutable = Self::data_values(data.mutable(), addresses)?;
+            supply.push(ManifestValue::Composite(
+                String::from("NonFungibleLocalId"),
+                vec![ManifestValue::String(Content::Literal(id.to_string()))],
+            ));
+            supply.push(ManifestValue::Composite(
+                String::from("Tuple"),
+                vec![
+                    ManifestValue::Composite(String::from("Tuple"), immutable),
+                    ManifestValue::Composite(String::from("Tuple"), mutable),
+                ],
+            ));
+        }
+
+        let inst = Instruction::CreateNonFungibleResource {
+            id_type: Self::enum_value(&format!("NonFungibleIdType::{}", id_type), vec![]),
+            metadata: ManifestValue::Composite(
+                String::from("Map<String, String>"),
+                vec![
+                    ManifestValue::String(Content::Literal(String::from("name"))),
+                    ManifestValue::String(Content::Literal(name.to_string())),
+                ],
+            ),
+            access_rules: ManifestValue::Composite(String::from("Map<Enum, Tuple>"), access_rules),
+            initial_supply: ManifestValue::Composite(
+                String::from("Some"),
+                vec![ManifestValue::Composite(
+                    String::from("Map<NonFungibleLocalId, Tuple>"),
+                    supply,
+                )],
+            ),
+        };
+        self.instructions.push(inst);
+        Ok(())
+    }
+
+    /// Mints fungible tokens, deposited in the account of the caller by [`Manifest::finish`]
+    ///
+    /// # Arguments
+    /// * `resource_address` - address of the resource
+    /// * `amount` - amount to mint
+    /// * `minter_badge_arg` - variable bound to the address of the badge allowed to mint, if any
+    pub fn mint_fungible(
+        &mut self,
+        resource_address: &str,
+        amount: Decimal,
+        minter_badge_arg: Option<&str>,
+    ) {
+        self.prove_minter(minter_badge_arg);
+        let inst = Instruction::MintFungible {
+            resource_address: ManifestValue::custom("ResourceAddress", resource_address),
+            amount: ManifestValue::decimal(amount),
+        };
+        self.instructions.push(inst);
+    }
+
+    /// Burns tokens taken from the account of the caller
+    ///
+    /// # Arguments
+    /// * `resource_address` - address of the resource
+    /// * `amount` - amount to burn
+    /// * `minter_badge_arg` - variable bound to the address of the badge allowed to burn, if any
+    pub fn burn(
+        &mut self,
+        resource_address: &str,
+        amount: Decimal,
+        minter_badge_arg: Option<&str>,
+    ) {
+        self.prove_minter(minter_badge_arg);
+        self.withdraw_by_amount(Self::caller_arg(), amount, resource_address);
+        self.take_from_worktop_by_amount(amount, resource_address, self.id);
+        let inst = Instruction::BurnResource {
+            bucket: self.id.to_string(),
+        };
+        self.id += 1;
+        self.instructions.push(inst);
+    }
+
+    /// Sets a metadata field of a resource
+    ///
+    /// # Arguments
+    /// * `resource_address` - address of the resource
+    /// * `key` - key of the field
+    /// * `value` - value of the field
+    /// * `minter_badge_arg` - variable bound to the address of the badge allowed to update the metadata, if any
+    pub fn set_metadata(
+        &mut self,
+        resource_address: &str,
+        key: &str,
+        value: &str,
+        minter_badge_arg: Option<&str>,
+    ) {
+        self.prove_minter(minter_badge_arg);
+        let inst = Instruction::SetMetadata {
+            entity_address: ManifestValue::custom("ResourceAddress", resource_address),
+            key: ManifestValue::String(Content::Literal(key.to_string())),
+            value: ManifestValue::String(Content::Literal(value.to_string())),
+        };
+        self.instructions.push(inst);
+    }
+
+    fn prove_minter(&mut self, minter_badge_arg: Option<&str>) {
+        if let Some(badge_arg) = minter_badge_arg {
+            self.create_admin_badge_proof(Self::caller_arg(), badge_arg);
+        }
+    }
+
+    /// Returns an enum value, such as `Enum("AccessRule::AllowAll")`
+    fn enum_value(variant: &str, fields: Vec<ManifestValue>) -> ManifestValue {
+        let mut elements = vec![ManifestValue::String(Content::Literal(variant.to_string()))];
+        elements.extend(fields);
+        ManifestValue::Composite(String::from("Enum"), elements)
+    }
+
+    fn data_values(
+        fields: &[Arg],
+        addresses: &dyn AddressBook,
+    ) -> Result<Vec<ManifestValue>, SqrtError> {
+        fields
+            .iter()
+            .map(|field| field.to_manifest_value(addresses))
+            .collect()
+    }
+
+    /// Locks the fee of the [`FeeLock`] of the manifest, the payer being bound to the fee payer variable
+    pub fn lock_bound_fee(&mut self) {
+        let inst = Instruction::CallMethod {
+            component_address: ManifestValue::custom_variable(
+                "ComponentAddress",
+                &Self::fee_payer_arg(),
+            ),
+            method_name: self.fee_method.clone(),
+            args: vec![ManifestValue::decimal(self.fee_amount)],
         };
         self.needed_resources.push(inst);
     }
 
     fn take_from_worktop_by_amount(
         &mut self,
-        amount_arg: String,
-        resource_address_arg: String,
+        amount: Decimal,
+        resource_address: &str,
         bucket_id: u32,
     ) -> &mut Self {
         let inst = Instruction::TakeFromWorktopByAmount {
-            amount_arg,
-            resource_address_arg,
-            bucket_id,
+            amount: ManifestValue::decimal(amount),
+            resource_address: ManifestValue::custom("ResourceAddress", resource_address),
+            bucket: bucket_id.to_string(),
         };
         self.needed_resources.push(inst);
 
         self
     }
 
-    fn take_from_worktop_by_ids(
-        &mut self,
-        resource_address_arg: String,
-        ids_arg: String,
-        bucket_id: u32,
-    ) {
+    fn take_from_worktop_by_ids(&mut self, resource_address: &str, ids: &[String], bucket_id: u32) {
         let inst = Instruction::TakeFromWorktopByIds {
-            ids_arg,
-            resource_address_arg,
-            bucket_id,
+            ids: ManifestValue::ids(ids),
+            resource_address: ManifestValue::custom("ResourceAddress", resource_address),
+            bucket: bucket_id.to_string(),
         };
 
         self.needed_resources.push(inst);
     }
 
-    fn withdraw_by_amount(
-        &mut self,
-        account_arg: String,
-        amount_arg: String,
-        resource_address_arg: String,
-    ) {
+    fn withdraw_by_amount(&mut self, account_arg: String, amount: Decimal, resource_address: &str) {
         let inst = Instruction::CallMethod {
-            component_address_arg: account_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &account_arg),
             method_name: "withdraw_by_amount".to_string(),
             args: vec![
-                format!("Decimal(\"${{{}}}\")", amount_arg),
-                format!("ResourceAddress(\"${{{}}}\")", resource_address_arg),
+                ManifestValue::decimal(amount),
+                ManifestValue::custom("ResourceAddress", resource_address),
             ],
         };
         self.needed_resources.push(inst);
     }
 
-    fn withdraw_by_ids(
-        &mut self,
-        account_arg: String,
-        resource_address_arg: String,
-        ids_arg: String,
-    ) {
+    fn withdraw_by_ids(&mut self, account_arg: String, resource_address: &str, ids: &[String]) {
         let inst = Instruction::CallMethod {
-            component_address_arg: account_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &account_arg),
             method_name: "withdraw_by_ids".to_string(),
             args: vec![
-                format!("Array<NonFungibleLocalId>(${{{}}})", ids_arg),
-                format!("ResourceAddress(\"${{{}}}\")", resource_address_arg),
+                ManifestValue::ids(ids),
+                ManifestValue::custom("ResourceAddress", resource_address),
             ],
         };
 
         self.needed_resources.push(inst);
     }
 
-    fn create_admin_badge_proof(&mut self, account_arg: String, resource_address_arg: String) {
+    fn create_admin_badge_proof(&mut self, account_arg: String, badge_arg: &str) {
         let inst = Instruction::CallMethod {
-            component_address_arg: account_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &account_arg),
             method_name: "create_proof".to_string(),
-            args: vec![format!(
-                "ResourceAddress(\"${{{}}}\")",
-                resource_address_arg
-            )],
+            args: vec![ManifestValue::custom_variable("ResourceAddress", badge_arg)],
         };
         self.needed_resources.push(inst);
     }
@@ -158,15 +426,15 @@ impl Manifest {
     fn create_fungible_proof(
         &mut self,
         account_arg: String,
-        resource_address_arg: String,
-        amount_arg: String,
+        resource_address: &str,
+        amount: Decimal,
     ) {
         let inst = Instruction::CallMethod {
-            component_address_arg: account_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &account_arg),
             method_name: "create_proof_by_amount".to_string(),
             args: vec![
-                format!("Decimal(\"${{{}}}\")", amount_arg),
-                format!("ResourceAddress(\"${{{}}}\")", resource_address_arg),
+                ManifestValue::decimal(amount),
+                ManifestValue::custom("ResourceAddress", resource_address),
             ],
         };
         self.needed_resources.push(inst);
@@ -175,16 +443,15 @@ impl Manifest {
     pub fn create_non_fungible_proof(
         &mut self,
         account_arg: String,
-        resource_address_arg: String,
-        id_arg: String,
+        resource_address: &str,
+        ids: &[String],
     ) {
-        let resource_arg = format!("ResourceAddress(\"${{{}}}\")", resource_address_arg);
         let inst = Instruction::CallMethod {
-            component_address_arg: account_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &account_arg),
             method_name: "create_proof_by_ids".to_string(),
             args: vec![
-                format!("Array<NonFungibleLocalId>(${{{}}})", id_arg),
-                resource_arg,
+                ManifestValue::ids(ids),
+                ManifestValue::custom("ResourceAddress", resource_address),
             ],
         };
         self.needed_resources.push(inst);
@@ -193,20 +460,16 @@ impl Manifest {
     fn create_usable_fungible_proof(
         &mut self,
         account_arg: String,
-        resource_address_arg: String,
-        amount_arg: String,
+        resource_address: &str,
+        amount: Decimal,
         proof_id: u32,
     ) {
-        self.create_fungible_proof(
-            account_arg,
-            resource_address_arg.clone(),
-            amount_arg.clone(),
-        );
+        self.create_fungible_proof(account_arg, resource_address, amount);
 
         let inst = Instruction::CreateProofFromAuthZoneByAmount {
-            amount_arg,
-            resource_address_arg,
-            proof_id,
+            amount: ManifestValue::decimal(amount),
+            resource_address: ManifestValue::custom("ResourceAddress", resource_address),
+            proof: proof_id.to_string(),
         };
 
         self.needed_resources.push(inst);
@@ -215,25 +478,25 @@ impl Manifest {
     fn create_usable_non_fungible_proof(
         &mut self,
         account_arg: String,
-        resource_address_arg: String,
-        ids_arg: String,
+        resource_address: &str,
+        ids: &[String],
         proof_id: u32,
     ) {
-        self.create_non_fungible_proof(account_arg, resource_address_arg.clone(), ids_arg.clone());
+        self.create_non_fungible_proof(account_arg, resource_address, ids);
 
         let inst = Instruction::CreateProofFromAuthZoneByIds {
-            ids_arg,
-            resource_address_arg,
-            proof_id,
+            ids: ManifestValue::ids(ids),
+            resource_address: ManifestValue::custom("ResourceAddress", resource_address),
+            proof: proof_id.to_string(),
         };
         self.needed_resources.push(inst);
     }
 
     fn deposit_batch(&mut self, account_arg: String) {
         let inst = Instruction::CallMethod {
-            component_address_arg: account_arg,
+            component_address: ManifestValue::custom_variable("ComponentAddress", &account_arg),
             method_name: "deposit_batch".to_string(),
-            args: vec![String::from("Expression(\"ENTIRE_WORKTOP\")")],
+            args: vec![ManifestValue::expression("ENTIRE_WORKTOP")],
         };
 
         self.instructions.push(inst);
@@ -246,75 +509,82 @@ impl Manifest {
         }
     }
 
-    fn deal_with_args(&mut self, args: &Vec<Arg>) -> Vec<String> {
+    fn deal_with_args(
+        &mut self,
+        args: &[Arg],
+        addresses: &dyn AddressBook,
+    ) -> Result<Vec<ManifestValue>, SqrtError> {
         let mut args_vec = vec![];
 
         for arg in args {
             match arg {
-                Arg::FungibleBucketArg(_, _) => {
-                    let amount_arg = format!("arg_{}_amount", self.arg_count);
-                    let resource_arg = format!("arg_{}_resource", self.arg_count);
-                    self.withdraw_by_amount(
-                        Self::caller_arg(),
-                        amount_arg.clone(),
-                        resource_arg.clone(),
-                    );
-                    self.take_from_worktop_by_amount(amount_arg, resource_arg, self.id);
-                    let ret = format!("Bucket(\"{}\")", self.id);
+                Arg::FungibleBucketArg(name, amount) => {
+                    let resource_address = addresses.resource_address(name)?;
+                    self.withdraw_by_amount(Self::caller_arg(), *amount, &resource_address);
+                    self.take_from_worktop_by_amount(*amount, &resource_address, self.id);
+                    let ret = ManifestValue::bucket(&self.id.to_string());
+                    self.id += 1;
+                    args_vec.push(ret);
+                }
+                Arg::NonFungibleBucketArg(name, ids) => {
+                    let resource_address = addresses.resource_address(name)?;
+                    self.withdraw_by_ids(Self::caller_arg(), &resource_address, ids);
+                    self.take_from_worktop_by_ids(&resource_address, ids, self.id);
+                    let ret = ManifestValue::bucket(&self.id.to_string());
                     self.id += 1;
                     args_vec.push(ret);
                 }
-                Arg::NonFungibleBucketArg(_, _) => {
-                    let resource_arg = format!("arg_{}_resource", self.arg_count);
-                    let ids_arg = format!("arg_{}_ids", self.arg_count);
-                    self.withdraw_by_ids(Self::caller_arg(), resource_arg.clone(), ids_arg.clone());
-                    self.take_from_worktop_by_ids(resource_arg, ids_arg, self.id);
-                    let ret = format!("Bucket(\"{}\")", self.id);
+                Arg::WorktopBucketArg(name) => {
+                    let inst = Instruction::TakeFromWorktop {
+                        resource_address: ManifestValue::custom(
+                            "ResourceAddress",
+                            &addresses.resource_address(name)?,
+                        ),
+                        bucket: self.id.to_string(),
+                    };
+                    self.instructions.push(inst);
+                    let ret = ManifestValue::bucket(&self.id.to_string());
                     self.id += 1;
                     args_vec.push(ret);
                 }
-                Arg::FungibleProofArg(_, _) => {
-                    let amount_arg = format!("arg_{}_amount", self.arg_count);
-                    let resource_arg = format!("arg_{}_resource", self.arg_count);
+                Arg::FungibleProofArg(name, amount) => {
+                    let resource_address = addresses.resource_address(name)?;
                     self.create_usable_fungible_proof(
                         Self::caller_arg(),
-                        resource_arg,
-                        amount_arg,
+                        &resource_address,
+                        *amount,
                         self.id,
                     );
-                    let ret = format!("Proof(\"{}\")", self.id);
+                    let ret = ManifestValue::proof(&self.id.to_string());
                     self.id += 1;
                     self.has_proofs = true;
                     args_vec.push(ret);
                 }
-                Arg::NonFungibleProofArg(_, _) => {
-                    let ids_arg = format!("arg_{}_ids", self.arg_count);
-                    let resource_arg = format!("arg_{}_resource", self.arg_count);
+                Arg::NonFungibleProofArg(name, ids) => {
+                    let resource_address = addresses.resource_address(name)?;
                     self.create_usable_non_fungible_proof(
                         Self::caller_arg(),
-                        resource_arg,
-                        ids_arg,
+                        &resource_address,
+                        ids,
                         self.id,
                     );
-                    let ret = format!("Proof(\"{}\")", self.id);
+                    let ret = ManifestValue::proof(&self.id.to_string());
                     self.id += 1;
                     self.has_proofs = true;
                     args_vec.push(ret);
                 }
-                Arg::NonFungibleGlobalAddress(_, _) => {
-                    let resource_arg = format!("arg_{}_resource", self.arg_count);
-                    let id_arg = format!("arg_{}_id", self.arg_count);
-                    let ret = format!("{}(\"{}\", {})", arg.get_type(), resource_arg, id_arg);
-                    args_vec.push(ret);
-                }
                 _ => {
-                    args_vec.push(arg.to_generic(self.arg_count));
+                    args_vec.push(arg.to_manifest_value(addresses)?);
                 }
             }
-            self.arg_count += 1;
         }
 
-        args_vec
+        Ok(args_vec)
+    }
+
+    /// Returns the instructions of the manifest, in the order they are run
+    pub fn instructions(&self) -> Vec<Instruction> {
+        [self.needed_resources.clone(), self.instructions.clone()].concat()
     }
 
     pub fn build(&self) -> String {
@@ -328,6 +598,7 @@ impl Manifest {
 
         output.pop();
         output.pop();
+
         output
     }
 
@@ -335,6 +606,10 @@ impl Manifest {
         String::from("caller_address")
     }
 
+    pub fn fee_payer_arg() -> String {
+        String::from("fee_payer_address")
+    }
+
     pub fn component_arg() -> String {
         String::from("component_address")
     }
@@ -347,3 +622,9 @@ impl Manifest {
         String::from("package_address")
     }
 }
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self::new()
+    }
+}
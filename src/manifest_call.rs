@@ -1,8 +1,16 @@
 //! Constructs a Manifest call
 
-use crate::error::Error;
+use crate::error::{Error, SqrtError};
+use crate::fee::FeeSummary;
+use crate::instructions::Instruction;
+use crate::manifest_value::ManifestValue;
+use crate::receipt::{BalanceChange, Level, Receipt, TransactionStatus};
+use crate::return_value::ReturnValue;
 use crate::test_environment::TestEnvironment;
-use crate::utils::run_manifest;
+use crate::utils::read_manifest;
+use lazy_static::lazy_static;
+use regex::Regex;
+use scrypto::prelude::Decimal;
 
 pub struct ManifestCall<'a> {
     test_environment: &'a mut TestEnvironment,
@@ -11,6 +19,10 @@ pub struct ManifestCall<'a> {
     env_bindings: Vec<(String, String)>,
     output_manifest: bool,
     expected_error: Error,
+    expected_balance_changes: Vec<(String, String, Decimal)>,
+    worktop_assertions: Vec<(String, Decimal)>,
+    cost_name: Option<String>,
+    expected_logs: Vec<(Level, Regex)>,
 }
 
 impl<'a> ManifestCall<'a> {
@@ -26,6 +38,10 @@ impl<'a> ManifestCall<'a> {
             env_bindings: vec![],
             output_manifest: false,
             expected_error: Error::Success,
+            expected_balance_changes: vec![],
+            worktop_assertions: vec![],
+            cost_name: None,
+            expected_logs: vec![],
         }
     }
 
@@ -69,26 +85,377 @@ impl<'a> ManifestCall<'a> {
         self
     }
 
-    /// Runs a [`ManifestCall`] and returns a [`String`] if required
-    pub fn run(self) -> Option<String> {
+    /// States that running the [`ManifestCall`] should change the balances of the given accounts by the given amounts
+    ///
+    /// Balances that are not listed are not checked.
+    /// # Arguments
+    /// * `changes` - vector of (account name, resource name, expected change of the balance)
+    pub fn expect_balance_changes(
+        mut self,
+        changes: Vec<(&str, &str, Decimal)>,
+    ) -> ManifestCall<'a> {
+        for (account_name, resource_name, delta) in changes {
+            self.expected_balance_changes.push((
+                account_name.to_string(),
+                resource_name.to_string(),
+                delta,
+            ));
+        }
+        self
+    }
+
+    /// Asserts that the worktop contains at least the given amount of a resource before it is deposited
+    ///
+    /// The assertion is added to the manifest with `ASSERT_WORKTOP_CONTAINS_BY_AMOUNT`, so the transaction fails
+    /// on-ledger when it does not hold.
+    /// # Arguments
+    /// * `resource_name` - name of the resource
+    /// * `amount` - minimal amount of the resource on the worktop
+    pub fn assert_worktop_contains(
+        mut self,
+        resource_name: &str,
+        amount: Decimal,
+    ) -> ManifestCall<'a> {
+        self.worktop_assertions
+            .push((resource_name.to_string(), amount));
+        self
+    }
+
+    /// States that a component should log a message of the given level containing the given text
+    ///
+    /// # Arguments
+    /// * `level` - level of the log
+    /// * `message` - text contained in the message
+    pub fn expect_log(mut self, level: Level, message: &str) -> ManifestCall<'a> {
+        self.expected_logs
+            .push((level, Regex::new(&regex::escape(message)).unwrap()));
+        self
+    }
+
+    /// States that a component should log a message of the given level matching the given regex
+    ///
+    /// # Arguments
+    /// * `level` - level of the log
+    /// * `pattern` - regex the message should match
+    pub fn expect_log_matching(mut self, level: Level, pattern: &str) -> ManifestCall<'a> {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|error| panic!("Invalid log pattern {}: {}", pattern, error));
+        self.expected_logs.push((level, regex));
+        self
+    }
+
+    /// Records the cost units consumed by the [`ManifestCall`] under the given name
+    ///
+    /// The cost is compared to the baseline of the package as configured by
+    /// [`TestEnvironment::set_cost_tracking`].
+    /// # Arguments
+    /// * `name` - name of the call in the recorded costs
+    pub fn track_cost(mut self, name: &str) -> ManifestCall<'a> {
+        self.cost_name = Some(name.to_string());
+        self
+    }
+
+    /// Runs a [`ManifestCall`] and returns its [`Receipt`]
+    ///
+    /// The receipt contains the run manifest if it was required with [`ManifestCall::output_manifest`].
+    pub fn run(self) -> Receipt {
+        self.run_or_panic()
+    }
+
+    /// Runs a [`ManifestCall`] and returns its [`Receipt`], or an error if resim could not run it or if the
+    /// transaction failed while no error was expected
+    ///
+    /// Other expectations of the call, such as balance changes or logs, still panic when they are not met.
+    pub fn try_run(self) -> Result<Receipt, SqrtError> {
+        self.execute(true)
+    }
+
+    /// Runs a [`ManifestCall`] and returns the values returned by every instruction of the manifest
+    pub fn run_with_outputs(self) -> Vec<ReturnValue> {
+        self.run_or_panic().outputs
+    }
+
+    /// Runs a [`ManifestCall`] and returns the fees paid by the transaction
+    pub fn run_with_fees(self) -> FeeSummary {
+        let receipt = self.run_or_panic();
+        match receipt.fees {
+            None => panic!(
+                "Could not find the fees in the transaction receipt:\n{}",
+                receipt.stdout
+            ),
+            Some(fees) => fees,
+        }
+    }
+
+    fn run_or_panic(self) -> Receipt {
+        self.execute(false)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    fn execute(self, fallible: bool) -> Result<Receipt, SqrtError> {
         if self.manifest_name.is_none() || self.custom_manifest.is_none() {
             panic!("Cannot run a manifest without specifying what to call")
         }
 
-        let (manifest_output, stdout, stderr) = run_manifest(
-            self.test_environment.get_current_package().path(),
-            self.manifest_name.unwrap().as_str(),
-            self.custom_manifest.unwrap(),
-            self.env_bindings,
-        );
-        self.expected_error.check_error(stdout, stderr);
+        let balances_before: Vec<Decimal> = self
+            .expected_balance_changes
+            .iter()
+            .map(|(account, resource, _)| self.test_environment.balance_of(account, resource))
+            .collect();
+        let caller_balances_before = self.test_environment.current_balances();
+
+        let (original_name, original_custom) =
+            (self.manifest_name.unwrap(), self.custom_manifest.unwrap());
+        let (mut manifest_name, mut custom_manifest) = (original_name.clone(), original_custom);
+        let env_bindings = self.env_bindings;
+
+        let mut assertions = vec![];
+        for (resource, amount) in &self.worktop_assertions {
+            assertions.push((
+                self.test_environment.try_get_resource(resource)?.clone(),
+                *amount,
+            ));
+        }
+
+        if !assertions.is_empty() {
+            manifest_name = Self::add_worktop_assertions(
+                self.test_environment,
+                &original_name,
+                original_custom,
+                &assertions,
+            )?;
+            custom_manifest = false;
+        }
+
+        let path = self
+            .test_environment
+            .manifest_path(&manifest_name, custom_manifest);
+        let (manifest_output, stdout, stderr) = self
+            .test_environment
+            .run_manifest(&path, env_bindings.clone())?;
+
+        lazy_static! {
+            static ref ASSERTION_FAILED_RE: Regex =
+                Regex::new(r"COMMITTED FAILURE: .*WorktopError\(AssertionFailed\)").unwrap();
+        }
+        let expects_success = matches!(self.expected_error, Error::Success);
+        // Without a transaction status, the manifest was not run and there is no receipt to check
+        let receipt = Receipt::from_stdout(&stdout);
+        if receipt.status == TransactionStatus::Unknown {
+            return Err(SqrtError::ResimFailure { stdout, stderr });
+        }
+        if fallible && expects_success && !receipt.is_success() {
+            self.test_environment.update();
+            return Err(SqrtError::TransactionFailed(Box::new(receipt)));
+        }
+
+        if expects_success && !assertions.is_empty() && ASSERTION_FAILED_RE.is_match(&stdout) {
+            // The failed transaction only committed its fee, so the ledger is saved after it and the assertions are
+            // added back one at a time until the transaction fails again
+            let snapshot = self.test_environment.backend_mut().snapshot();
+            let mut failed = assertions.len() - 1;
+            for count in 1..assertions.len() {
+                let assertions_name = Self::add_worktop_assertions(
+                    self.test_environment,
+                    &original_name,
+                    original_custom,
+                    &assertions[..count],
+                )?;
+                let path = self.test_environment.manifest_path(&assertions_name, false);
+                let output = self
+                    .test_environment
+                    .run_manifest(&path, env_bindings.clone());
+                self.test_environment.backend_mut().restore(&*snapshot);
+                let (_, stdout, _) = output?;
+                if ASSERTION_FAILED_RE.is_match(&stdout) {
+                    failed = count - 1;
+                    break;
+                }
+            }
+
+            let (resource, amount) = &self.worktop_assertions[failed];
+            panic!(
+                "Worktop assertion {} failed: expected at least {} {} on the worktop\n\
+                        Transaction Output: \n\n{}",
+                failed, amount, resource, stdout
+            );
+        }
+        self.expected_error.check_error(stdout.clone(), stderr);
         self.test_environment.update();
 
+        let mut receipt = receipt;
+        let caller_balances = self.test_environment.current_balances();
+        let mut resources: Vec<&String> = caller_balances
+            .keys()
+            .chain(caller_balances_before.keys())
+            .collect();
+        resources.sort();
+        resources.dedup();
+        for resource in resources {
+            let before = caller_balances_before
+                .get(resource)
+                .copied()
+                .unwrap_or_else(Decimal::zero);
+            let after = caller_balances
+                .get(resource)
+                .copied()
+                .unwrap_or_else(Decimal::zero);
+            if before != after {
+                receipt.balance_changes.push(BalanceChange {
+                    resource: resource.clone(),
+                    change: after - before,
+                });
+            }
+        }
+
+        Self::check_logs(&self.expected_logs, &receipt);
+
+        if let Some(name) = &self.cost_name {
+            match &receipt.fees {
+                None => panic!(
+                    "Could not find the fees in the transaction receipt:\n{}",
+                    stdout
+                ),
+                Some(fees) => self.test_environment.cost_tracking().record(
+                    self.test_environment.get_current_package().path(),
+                    name,
+                    fees.cost_units_consumed,
+                ),
+            }
+        }
+
+        if !self.expected_balance_changes.is_empty() {
+            let actual_changes: Vec<Decimal> = self
+                .expected_balance_changes
+                .iter()
+                .zip(balances_before)
+                .map(|((account, resource, _), before)| {
+                    self.test_environment.balance_of(account, resource) - before
+                })
+                .collect();
+            Self::check_balance_changes(&self.expected_balance_changes, &actual_changes);
+        }
+
         if self.output_manifest {
-            Some(manifest_output)
-        } else {
-            None
+            receipt.manifest = Some(manifest_output);
         }
+        Ok(receipt)
+    }
+
+    /// Writes a copy of a manifest checking the first worktop assertions before the resources are deposited and
+    /// returns the name of the generated manifest
+    ///
+    /// # Arguments
+    /// * `test_environment` - [`TestEnvironment`] of the call
+    /// * `name` - name of the manifest
+    /// * `custom_manifest` - whether the manifest is a custom one
+    /// * `assertions` - addresses of the resources with the minimal amounts to assert
+    fn add_worktop_assertions(
+        test_environment: &TestEnvironment,
+        name: &str,
+        custom_manifest: bool,
+        assertions: &[(String, Decimal)],
+    ) -> Result<String, SqrtError> {
+        let path = test_environment.manifest_path(name, custom_manifest);
+        let manifest = read_manifest(&path)?;
+        let mut instructions = Instruction::parse_manifest(&manifest)
+            .map_err(|error| SqrtError::ParseError(format!("{}: {}", path, error)))?;
+
+        let position = instructions
+            .iter()
+            .rposition(|instruction| {
+                matches!(instruction, Instruction::CallMethod { method_name, .. } if method_name == "deposit_batch")
+            })
+            .unwrap_or(instructions.len());
+        for (resource_address, amount) in assertions.iter().rev() {
+            instructions.insert(
+                position,
+                Instruction::AssertWorktopContainsByAmount {
+                    amount: ManifestValue::decimal(*amount),
+                    resource_address: ManifestValue::custom("ResourceAddress", resource_address),
+                },
+            );
+        }
+
+        let output: Vec<String> = instructions
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+        let assertions_name = format!("{}_assert_{}", name, assertions.len());
+        test_environment.write_manifest(output.join("\n\n"), &assertions_name);
+        Ok(assertions_name)
+    }
+
+    fn check_logs(expected: &[(Level, Regex)], receipt: &Receipt) {
+        for (level, regex) in expected {
+            let found = receipt
+                .logs
+                .iter()
+                .any(|log| log.level == *level && regex.is_match(&log.message));
+            if !found {
+                let logs: Vec<String> = receipt
+                    .logs
+                    .iter()
+                    .map(|log| format!("[{:?}] {}", log.level, log.message))
+                    .collect();
+                panic!(
+                    "No {:?} log matching `{}`\nLogs:\n{}",
+                    level,
+                    regex.as_str(),
+                    logs.join("\n")
+                );
+            }
+        }
+    }
+
+    fn check_balance_changes(expected: &[(String, String, Decimal)], actual: &[Decimal]) {
+        if expected
+            .iter()
+            .zip(actual)
+            .all(|((_, _, expected_change), actual_change)| expected_change == actual_change)
+        {
+            return;
+        }
+
+        let mut rows = vec![(
+            String::from("account"),
+            String::from("resource"),
+            String::from("expected"),
+            String::from("actual"),
+        )];
+        for ((account, resource, expected_change), actual_change) in expected.iter().zip(actual) {
+            rows.push((
+                account.clone(),
+                resource.clone(),
+                expected_change.to_string(),
+                actual_change.to_string(),
+            ));
+        }
+
+        let width = |column: fn(&(String, String, String, String)) -> &String| {
+            rows.iter().map(|row| column(row).len()).max().unwrap()
+        };
+        let (account_width, resource_width, expected_width) = (
+            width(|row| &row.0),
+            width(|row| &row.1),
+            width(|row| &row.2),
+        );
+
+        let mut table = String::new();
+        for (index, (account, resource, expected_change, actual_change)) in rows.iter().enumerate()
+        {
+            let marker = if index > 0 && expected_change != actual_change {
+                "  <-"
+            } else {
+                ""
+            };
+            table = format!(
+                "{}{:account_width$}  {:resource_width$}  {:>expected_width$}  {}{}\n",
+                table, account, resource, expected_change, actual_change, marker
+            );
+        }
+
+        panic!("Unexpected balance changes:\n{}", table);
     }
 
     /// Runs a [`ManifestCall`] and returns the call output
@@ -97,12 +464,13 @@ impl<'a> ManifestCall<'a> {
             panic!("Cannot debug a manifest without specifying what to call")
         }
 
-        let (_, stdout, stderr) = run_manifest(
-            self.test_environment.get_current_package().path(),
-            self.manifest_name.unwrap().as_str(),
-            self.custom_manifest.unwrap(),
-            self.env_bindings,
-        );
+        let path = self
+            .test_environment
+            .manifest_path(&self.manifest_name.unwrap(), self.custom_manifest.unwrap());
+        let (_, stdout, stderr) = self
+            .test_environment
+            .run_manifest(&path, self.env_bindings)
+            .unwrap_or_else(|error| panic!("{}", error));
         self.test_environment.update();
 
         (stdout, stderr)
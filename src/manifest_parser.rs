@@ -0,0 +1,260 @@
+//! Parser reading the instructions of a transaction manifest back into typed values
+
+use crate::instructions::Instruction;
+use crate::manifest_value::{Content, ManifestValue, COMPOSITE_KINDS, INTEGER_TYPES};
+
+pub(crate) struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Parser<'a> {
+        Parser { input, position: 0 }
+    }
+
+    /// Parses every instruction of the manifest
+    pub fn parse_instructions(&mut self) -> Result<Vec<Instruction>, String> {
+        let mut instructions = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek().is_none() {
+                return Ok(instructions);
+            }
+
+            let line = self.line();
+            let name = self.identifier();
+            if name.is_empty() {
+                return Err(self.error("an instruction"));
+            }
+
+            let mut operands = vec![];
+            loop {
+                self.skip_whitespace();
+                match self.peek() {
+                    None => return Err(self.error("`;`")),
+                    Some(';') => {
+                        self.position += 1;
+                        break;
+                    }
+                    Some(_) => operands.push(self.value()?),
+                }
+            }
+
+            let instruction = Instruction::from_operands(&name, operands)
+                .map_err(|error| format!("Line {}: {}", line, error))?;
+            instructions.push(instruction);
+        }
+    }
+
+    /// Parses a single value
+    pub fn value(&mut self) -> Result<ManifestValue, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.position += 1;
+                self.skip_whitespace();
+                self.expect(')')?;
+                Ok(ManifestValue::Unit)
+            }
+            Some('"') => Ok(ManifestValue::String(self.string()?)),
+            Some('$') => {
+                let name = self.variable()?;
+                match self.integer_type() {
+                    None => Ok(ManifestValue::Variable(name)),
+                    Some(integer_type) => Ok(ManifestValue::Integer(
+                        Content::Variable(name),
+                        integer_type,
+                    )),
+                }
+            }
+            Some(character) if character == '-' || character.is_ascii_digit() => {
+                let start = self.position;
+                self.position += 1;
+                while self
+                    .peek()
+                    .is_some_and(|character| character.is_ascii_digit())
+                {
+                    self.position += 1;
+                }
+                let digits = self.input[start..self.position].to_string();
+                match self.integer_type() {
+                    None => Err(self.error("the type of the integer")),
+                    Some(integer_type) => Ok(ManifestValue::Integer(
+                        Content::Literal(digits),
+                        integer_type,
+                    )),
+                }
+            }
+            Some(character) if character.is_ascii_alphabetic() => {
+                let mut type_name = self.identifier();
+                match type_name.as_str() {
+                    "true" => return Ok(ManifestValue::Bool(true)),
+                    "false" => return Ok(ManifestValue::Bool(false)),
+                    _ => {}
+                }
+                let kind = type_name.clone();
+                if self.peek() == Some('<') {
+                    type_name.push_str(&self.generics()?);
+                }
+
+                self.skip_whitespace();
+                if self.peek() != Some('(') {
+                    return Ok(ManifestValue::Keyword(type_name));
+                }
+                self.expect('(')?;
+                self.skip_whitespace();
+                if !COMPOSITE_KINDS.contains(&kind.as_str()) && self.peek() == Some('"') {
+                    let content = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(')')?;
+                    return Ok(ManifestValue::Custom(type_name, content));
+                }
+
+                let mut elements = vec![];
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() == Some(')') {
+                        self.position += 1;
+                        break;
+                    }
+                    if !elements.is_empty() {
+                        self.expect(',')?;
+                    }
+                    elements.push(self.value()?);
+                }
+                Ok(ManifestValue::Composite(type_name, elements))
+            }
+            _ => Err(self.error("a value")),
+        }
+    }
+
+    fn string(&mut self) -> Result<Content, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                None => return Err(self.error("`\"`")),
+                Some('"') => break,
+                Some('\\') => match self.next() {
+                    None => return Err(self.error("an escaped character")),
+                    Some(character) => value.push(character),
+                },
+                Some(character) => value.push(character),
+            }
+        }
+
+        // A string only made of a variable is bound to that variable
+        let is_variable = value.starts_with("${")
+            && value.ends_with('}')
+            && value[2..value.len() - 1].chars().all(is_identifier_char);
+        if is_variable {
+            Ok(Content::Variable(value[2..value.len() - 1].to_string()))
+        } else {
+            Ok(Content::Literal(value))
+        }
+    }
+
+    fn variable(&mut self) -> Result<String, String> {
+        self.expect('$')?;
+        self.expect('{')?;
+        let name = self.identifier();
+        self.expect('}')?;
+        Ok(name)
+    }
+
+    fn integer_type(&mut self) -> Option<String> {
+        let start = self.position;
+        let suffix = self.identifier();
+        if INTEGER_TYPES.contains(&suffix.as_str()) {
+            Some(suffix)
+        } else {
+            self.position = start;
+            None
+        }
+    }
+
+    /// Reads generic parameters, such as `<NonFungibleLocalId>`
+    fn generics(&mut self) -> Result<String, String> {
+        let start = self.position;
+        let mut depth = 0;
+        loop {
+            match self.next() {
+                None => return Err(self.error("`>`")),
+                Some('<') => depth += 1,
+                Some('>') => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(self.input[start..self.position].to_string());
+                    }
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.position;
+        while self.peek().is_some_and(is_identifier_char) {
+            self.position += 1;
+        }
+        self.input[start..self.position].to_string()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(character) if character.is_whitespace() => {
+                    self.position += character.len_utf8()
+                }
+                // Comments run until the end of the line
+                Some('#') => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.next();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() == Some(expected) {
+            self.position += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("`{}`", expected)))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let character = self.peek()?;
+        self.position += character.len_utf8();
+        Some(character)
+    }
+
+    fn line(&self) -> usize {
+        self.input[..self.position].matches('\n').count() + 1
+    }
+
+    fn error(&self, expected: &str) -> String {
+        let found = match self.peek() {
+            None => String::from("the end of the manifest"),
+            Some(character) => format!("`{}`", character),
+        };
+        format!(
+            "Line {}: expected {}, found {}",
+            self.line(),
+            expected,
+            found
+        )
+    }
+}
+
+fn is_identifier_char(character: char) -> bool {
+    character.is_ascii_alphanumeric() || character == '_'
+}
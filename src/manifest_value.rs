@@ -0,0 +1,193 @@
+//! Typed values of the arguments of a transaction manifest
+
+use scrypto::prelude::Decimal;
+use std::fmt::{Display, Formatter};
+
+/// Content of a value, either known when the manifest is built or bound to a variable when it is run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Content {
+    Literal(String),
+    /// Name of the variable, rendered as `${name}`
+    Variable(String),
+}
+
+/// Value of an argument of a transaction manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestValue {
+    Unit,
+    Bool(bool),
+    /// Integer with its type, rendered as `5u8`
+    Integer(Content, String),
+    String(Content),
+    /// Value of a Scrypto custom type built from a string, rendered as `Decimal("1")` or `Bucket("0")`
+    Custom(String, Content),
+    /// Value made of other values, such as `Tuple(1u8, "a")`, `Enum(0u8)` or `Array<Decimal>(Decimal("1"))`
+    Composite(String, Vec<ManifestValue>),
+    /// Variable that is replaced by one or more values when the manifest is run, rendered as `${name}`
+    Variable(String),
+    /// Value written as a bare name, such as `None`
+    Keyword(String),
+}
+
+/// Values rendered as [`ManifestValue::Composite`] even when they contain a single string
+pub(crate) const COMPOSITE_KINDS: [&str; 9] = [
+    "Enum",
+    "Tuple",
+    "Array",
+    "Map",
+    "Some",
+    "Ok",
+    "Err",
+    "NonFungibleLocalId",
+    "NonFungibleGlobalAddress",
+];
+
+/// Types an integer literal can end with
+pub(crate) const INTEGER_TYPES: [&str; 10] = [
+    "i8", "i16", "i32", "i64", "i128", "u8", "u16", "u32", "u64", "u128",
+];
+
+impl ManifestValue {
+    /// Returns a value of the given custom type bound to a variable, such as `ResourceAddress("${name}")`
+    ///
+    /// # Arguments
+    /// * `type_name` - name of the custom type
+    /// * `variable` - name of the variable
+    pub fn custom_variable(type_name: &str, variable: &str) -> ManifestValue {
+        ManifestValue::Custom(
+            type_name.to_string(),
+            Content::Variable(variable.to_string()),
+        )
+    }
+
+    /// Returns a value of the given custom type, such as `ResourceAddress("resource_sim1...")`
+    ///
+    /// # Arguments
+    /// * `type_name` - name of the custom type
+    /// * `value` - content of the value
+    pub fn custom(type_name: &str, value: &str) -> ManifestValue {
+        ManifestValue::Custom(type_name.to_string(), Content::Literal(value.to_string()))
+    }
+
+    /// Returns a `Decimal` value
+    ///
+    /// # Arguments
+    /// * `amount` - value of the decimal
+    pub fn decimal(amount: Decimal) -> ManifestValue {
+        ManifestValue::Custom(
+            String::from("Decimal"),
+            Content::Literal(amount.to_string()),
+        )
+    }
+
+    /// Returns a `Bucket` value
+    ///
+    /// # Arguments
+    /// * `name` - name of the bucket
+    pub fn bucket(name: &str) -> ManifestValue {
+        ManifestValue::Custom(String::from("Bucket"), Content::Literal(name.to_string()))
+    }
+
+    /// Returns a `Proof` value
+    ///
+    /// # Arguments
+    /// * `name` - name of the proof
+    pub fn proof(name: &str) -> ManifestValue {
+        ManifestValue::Custom(String::from("Proof"), Content::Literal(name.to_string()))
+    }
+
+    /// Returns an `Expression` value, such as `Expression("ENTIRE_WORKTOP")`
+    ///
+    /// # Arguments
+    /// * `expression` - the expression
+    pub fn expression(expression: &str) -> ManifestValue {
+        ManifestValue::Custom(
+            String::from("Expression"),
+            Content::Literal(expression.to_string()),
+        )
+    }
+
+    /// Returns an array of non fungible ids, such as `Array<NonFungibleLocalId>(NonFungibleLocalId("#1#"))`
+    ///
+    /// # Arguments
+    /// * `ids` - the non fungible ids
+    pub fn ids(ids: &[String]) -> ManifestValue {
+        ManifestValue::Composite(
+            String::from("Array<NonFungibleLocalId>"),
+            ids.iter()
+                .map(|id| {
+                    ManifestValue::Composite(
+                        String::from("NonFungibleLocalId"),
+                        vec![ManifestValue::String(Content::Literal(id.clone()))],
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the text bound to a variable of a custom manifest to give it this value
+    ///
+    /// Variables are written inside the value in custom manifests, such as `Decimal("${amount}")` or
+    /// `Tuple(${fields})`, so only the content of the value is bound, as is: strings are not escaped, a
+    /// `NonFungibleLocalId` is bound to its id and a `NonFungibleGlobalAddress` to `resource_address, id`.
+    pub fn binding(&self) -> String {
+        match self {
+            ManifestValue::Unit => String::new(),
+            ManifestValue::Integer(content, _)
+            | ManifestValue::String(content)
+            | ManifestValue::Custom(_, content) => content.to_string(),
+            ManifestValue::Composite(type_name, elements)
+                if type_name == "NonFungibleLocalId" || type_name == "NonFungibleGlobalAddress" =>
+            {
+                let elements: Vec<String> =
+                    elements.iter().map(|element| element.binding()).collect();
+                elements.join(", ")
+            }
+            ManifestValue::Composite(_, elements) => {
+                let elements: Vec<String> =
+                    elements.iter().map(|element| element.to_string()).collect();
+                elements.join(", ")
+            }
+            value => value.to_string(),
+        }
+    }
+}
+
+impl Display for Content {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Content::Literal(value) => write!(f, "{}", value),
+            Content::Variable(name) => write!(f, "${{{}}}", name),
+        }
+    }
+}
+
+impl Display for ManifestValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestValue::Unit => write!(f, "()"),
+            ManifestValue::Bool(value) => write!(f, "{}", value),
+            ManifestValue::Integer(content, integer_type) => {
+                write!(f, "{}{}", content, integer_type)
+            }
+            ManifestValue::String(content) => write!(f, "\"{}\"", escape(content)),
+            ManifestValue::Custom(type_name, content) => {
+                write!(f, "{}(\"{}\")", type_name, escape(content))
+            }
+            ManifestValue::Composite(type_name, elements) => {
+                let elements: Vec<String> =
+                    elements.iter().map(|element| element.to_string()).collect();
+                write!(f, "{}({})", type_name, elements.join(", "))
+            }
+            ManifestValue::Variable(name) => write!(f, "${{{}}}", name),
+            ManifestValue::Keyword(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+fn escape(content: &Content) -> String {
+    match content {
+        Content::Literal(value) => value.replace('\\', "\\\\").replace('"', "\\\""),
+        Content::Variable(_) => content.to_string(),
+    }
+}
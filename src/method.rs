@@ -1,8 +1,13 @@
 //! Defines methods that can be called for a blueprint
 
+use crate::error::SqrtError;
+use crate::manifest_value::{Content, ManifestValue};
 use scrypto::prelude::{Decimal, PreciseDecimal};
 use std::collections::HashMap;
 
+/// Derives the [`Method`] trait for an enum, see the documentation of the `sqrt-derive` crate
+pub use sqrt_derive::Method;
+
 /// Trait to implement to declare a new blueprint method
 pub trait Method {
     /// Returns the name of the method
@@ -18,6 +23,21 @@ pub trait Method {
     fn custom_manifest_name(&self) -> Option<&str>;
 }
 
+/// Resolves the names given to the entities of a test environment to their addresses
+pub trait AddressBook {
+    /// Returns the address of the package with the given name
+    fn package_address(&self, name: &str) -> Result<String, SqrtError>;
+
+    /// Returns the address of the component with the given name
+    fn component_address(&self, name: &str) -> Result<String, SqrtError>;
+
+    /// Returns the address of the account with the given name
+    fn account_address(&self, name: &str) -> Result<String, SqrtError>;
+
+    /// Returns the address of the resource with the given name
+    fn resource_address(&self, name: &str) -> Result<String, SqrtError>;
+}
+
 #[derive(Clone)]
 /// Possible arguments for a method call
 pub enum Arg {
@@ -56,6 +76,8 @@ pub enum Arg {
     FungibleBucketArg(String, Decimal),
     /// Represents a Bucket containing some Fungible Resource with given ids. The [String] should be the name of the resource according to the TestEnvironment (**NOT** the ResourceAddress) and the [Vec] should contain the ids of the NFR to put inside the Bucket
     NonFungibleBucketArg(String, Vec<String>),
+    /// Represents a Bucket taking all the tokens of a resource from the worktop, such as the tokens returned by a previous call of the same transaction. The [String] should be the name of the resource according to the TestEnvironment (**NOT** the ResourceAddress)
+    WorktopBucketArg(String),
     /// Represents a Proof a Fungible Resource. The [String] should be the name of the resource according to the TestEnvironment (**NOT** the ResourceAddress) and the [Decimal] the amount to use as proof
     FungibleProofArg(String, Decimal),
     /// Represents a Proof a Non Fungible Resource of given ids. The [String] should be the name of the resource according to the TestEnvironment (**NOT** the ResourceAddress) and the [Vec] should contain the ids of the NFR to build a proof of
@@ -107,6 +129,7 @@ impl Arg {
             Arg::SystemAddressArg(_) => String::from("SystemAddress"),
             Arg::FungibleBucketArg(_, _) => String::from("Bucket"),
             Arg::NonFungibleBucketArg(_, _) => String::from("Bucket"),
+            Arg::WorktopBucketArg(_) => String::from("Bucket"),
             Arg::FungibleProofArg(_, _) => String::from("Proof"),
             Arg::NonFungibleProofArg(_, _) => String::from("Proof"),
             Arg::Expression(_) => String::from("Expression"),
@@ -123,58 +146,113 @@ impl Arg {
         }
     }
 
-    /// Returns the generic form of an `Arg` for a Transaction Manifest
-    pub fn to_generic(&self, arg_count: u32) -> String {
-        let generic = format!("${{arg_{}}}", arg_count);
-        match self {
-            Arg::Unit => String::from("()"),
-            Arg::Bool(_) => generic,
-            Arg::I8(_)
-            | Arg::I16(_)
-            | Arg::I32(_)
-            | Arg::I64(_)
-            | Arg::I128(_)
-            | Arg::U8(_)
-            | Arg::U16(_)
-            | Arg::U32(_)
-            | Arg::U64(_)
-            | Arg::U128(_) => {
-                format!("{}{}", generic, self.get_type())
+    /// Returns the value of an `Arg` in a Transaction Manifest, the names of the entities being replaced by their
+    /// addresses
+    ///
+    /// Buckets and proofs are created by the instructions of the manifest, so they can only be arguments of a call.
+    /// # Arguments
+    /// * `addresses` - addresses of the entities of the test environment
+    pub fn to_manifest_value(
+        &self,
+        addresses: &dyn AddressBook,
+    ) -> Result<ManifestValue, SqrtError> {
+        let literal = |value: &str| Content::Literal(value.to_string());
+        let value = match self {
+            Arg::Unit => ManifestValue::Unit,
+            Arg::Bool(value) => ManifestValue::Bool(*value),
+            Arg::I8(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::I16(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::I32(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::I64(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::I128(value) => {
+                ManifestValue::Integer(literal(&value.to_string()), self.get_type())
+            }
+            Arg::U8(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::U16(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::U32(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::U64(value) => ManifestValue::Integer(literal(&value.to_string()), self.get_type()),
+            Arg::U128(value) => {
+                ManifestValue::Integer(literal(&value.to_string()), self.get_type())
+            }
+            Arg::StringArg(value) => ManifestValue::String(literal(value)),
+            Arg::EnumArg(variant, fields) => {
+                let mut elements = vec![ManifestValue::Integer(
+                    literal(&variant.to_string()),
+                    String::from("u8"),
+                )];
+                elements.extend(Self::to_manifest_values(fields, addresses)?);
+                ManifestValue::Composite(self.get_type(), elements)
+            }
+            Arg::TupleArg(elements) | Arg::VecArg(elements) => ManifestValue::Composite(
+                self.get_type(),
+                Self::to_manifest_values(elements, addresses)?,
+            ),
+            Arg::HashMapArg(entries) => {
+                let mut elements = vec![];
+                for (key, value) in entries {
+                    elements.push(ManifestValue::Composite(
+                        String::from("Tuple"),
+                        vec![
+                            key.to_manifest_value(addresses)?,
+                            value.to_manifest_value(addresses)?,
+                        ],
+                    ));
+                }
+                ManifestValue::Composite(self.get_type(), elements)
             }
-            Arg::StringArg(_) => {
-                format!("\"{}\"", generic)
+            Arg::PackageAddressArg(name) => {
+                ManifestValue::custom(&self.get_type(), &addresses.package_address(name)?)
             }
-            Arg::EnumArg(_, _)
-            | Arg::TupleArg(_)
-            | Arg::VecArg(_)
-            | Arg::HashMapArg(_)
-            | Arg::FungibleBucketArg(_, _)
+            Arg::ComponentAddressArg(name) => {
+                ManifestValue::custom(&self.get_type(), &addresses.component_address(name)?)
+            }
+            Arg::AccountAddressArg(name) => {
+                ManifestValue::custom(&self.get_type(), &addresses.account_address(name)?)
+            }
+            Arg::ResourceAddressArg(name) => {
+                ManifestValue::custom(&self.get_type(), &addresses.resource_address(name)?)
+            }
+            Arg::SystemAddressArg(value)
+            | Arg::Expression(value)
+            | Arg::Blob(value)
+            | Arg::HashArg(value)
+            | Arg::EcdsaSecp256k1PublicKeyArg(value)
+            | Arg::EcdsaSecp256k1Signature(value)
+            | Arg::EddsaEd25519PublicKey(value)
+            | Arg::EddsaEd25519Signature(value) => ManifestValue::custom(&self.get_type(), value),
+            Arg::DecimalArg(value) => ManifestValue::decimal(*value),
+            Arg::PreciseDecimalArg(value) => {
+                ManifestValue::custom(&self.get_type(), &value.to_string())
+            }
+            Arg::NonFungibleLocalId(id) => {
+                ManifestValue::Composite(self.get_type(), vec![id.to_manifest_value(addresses)?])
+            }
+            Arg::NonFungibleGlobalAddress(name, id) => ManifestValue::Composite(
+                self.get_type(),
+                vec![
+                    ManifestValue::String(literal(&addresses.resource_address(name)?)),
+                    id.to_manifest_value(addresses)?,
+                ],
+            ),
+            Arg::FungibleBucketArg(_, _)
             | Arg::NonFungibleBucketArg(_, _)
+            | Arg::WorktopBucketArg(_)
             | Arg::FungibleProofArg(_, _)
             | Arg::NonFungibleProofArg(_, _) => {
-                format!("{}({})", self.get_type(), generic)
-            }
-            Arg::PackageAddressArg(_)
-            | Arg::ComponentAddressArg(_)
-            | Arg::AccountAddressArg(_)
-            | Arg::ResourceAddressArg(_)
-            | Arg::SystemAddressArg(_)
-            | Arg::Expression(_)
-            | Arg::Blob(_)
-            | Arg::NonFungibleGlobalAddress(_, _)
-            | Arg::HashArg(_)
-            | Arg::EcdsaSecp256k1PublicKeyArg(_)
-            | Arg::EcdsaSecp256k1Signature(_)
-            | Arg::EddsaEd25519PublicKey(_)
-            | Arg::EddsaEd25519Signature(_)
-            | Arg::DecimalArg(_)
-            | Arg::PreciseDecimalArg(_) => {
-                format!("{}(\"{}\")", self.get_type(), generic)
+                panic!("Buckets and proofs can only be arguments of a call, not values inside another argument")
             }
-            Arg::NonFungibleLocalId(arg) => {
-                format!("{}({})", self.get_type(), arg.to_generic(arg_count))
-            }
-        }
+        };
+
+        Ok(value)
+    }
+
+    fn to_manifest_values(
+        args: &[Arg],
+        addresses: &dyn AddressBook,
+    ) -> Result<Vec<ManifestValue>, SqrtError> {
+        args.iter()
+            .map(|arg| arg.to_manifest_value(addresses))
+            .collect()
     }
 }
 
@@ -0,0 +1,79 @@
+//! Describes the non fungibles created by a [`TestEnvironment`](crate::test_environment::TestEnvironment)
+
+use crate::method::Arg;
+use std::fmt::{Display, Formatter};
+
+/// Local id of a non fungible
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LocalId {
+    /// Integer id, displayed as `#1#`
+    Integer(u64),
+    /// String id, displayed as `<name>`
+    String(String),
+    /// Bytes id, displayed as `[0a1b]`
+    Bytes(Vec<u8>),
+    /// UUID id, displayed as `{b36f5b3f-835b-406c-980f-7788d8f13c1b}`
+    Uuid(u128),
+}
+
+impl LocalId {
+    /// Returns the type of the id according to Transaction Manifests
+    pub fn id_type(&self) -> &str {
+        match self {
+            LocalId::Integer(_) => "Integer",
+            LocalId::String(_) => "String",
+            LocalId::Bytes(_) => "Bytes",
+            LocalId::Uuid(_) => "UUID",
+        }
+    }
+}
+
+impl Display for LocalId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LocalId::Integer(id) => write!(f, "#{}#", id),
+            LocalId::String(id) => write!(f, "<{}>", id),
+            LocalId::Bytes(id) => {
+                let hex: Vec<String> = id.iter().map(|byte| format!("{:02x}", byte)).collect();
+                write!(f, "[{}]", hex.join(""))
+            }
+            LocalId::Uuid(id) => {
+                let hex = format!("{:032x}", id);
+                write!(
+                    f,
+                    "{{{}-{}-{}-{}-{}}}",
+                    &hex[0..8],
+                    &hex[8..12],
+                    &hex[12..16],
+                    &hex[16..20],
+                    &hex[20..32]
+                )
+            }
+        }
+    }
+}
+
+/// Data of a non fungible, made of the fields of its immutable and mutable parts
+pub struct NonFungibleData {
+    immutable: Vec<Arg>,
+    mutable: Vec<Arg>,
+}
+
+impl NonFungibleData {
+    /// Returns a new NonFungibleData
+    ///
+    /// # Arguments
+    /// * `immutable` - values of the fields of the immutable part of the data
+    /// * `mutable` - values of the fields of the mutable part of the data
+    pub fn new(immutable: Vec<Arg>, mutable: Vec<Arg>) -> NonFungibleData {
+        NonFungibleData { immutable, mutable }
+    }
+
+    pub fn immutable(&self) -> &Vec<Arg> {
+        &self.immutable
+    }
+
+    pub fn mutable(&self) -> &Vec<Arg> {
+        &self.mutable
+    }
+}
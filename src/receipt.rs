@@ -0,0 +1,188 @@
+//! Parses the receipt of a transaction
+
+use crate::fee::FeeSummary;
+use crate::return_value::{strip_colors, ReturnValue};
+use lazy_static::lazy_static;
+use regex::Regex;
+use scrypto::prelude::Decimal;
+
+/// Status of a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// The transaction was committed and succeeded
+    Success,
+    /// The transaction was committed but failed with the given error
+    Failure(String),
+    /// The transaction was rejected with the given reason, so its fee was not paid
+    Rejection(String),
+    /// No known transaction status was found in the output, for instance because resim could not run the manifest
+    Unknown,
+}
+
+/// Level of a log message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Message logged by a component during a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub level: Level,
+    pub message: String,
+}
+
+/// Addresses of the entities created by a transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NewEntities {
+    pub packages: Vec<String>,
+    pub components: Vec<String>,
+    pub resources: Vec<String>,
+}
+
+/// Change of the amount of a resource owned by the account making a call
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChange {
+    /// Address of the resource
+    pub resource: String,
+    pub change: Decimal,
+}
+
+/// Receipt of a transaction
+#[derive(Debug, Clone)]
+pub struct Receipt {
+    pub status: TransactionStatus,
+    /// Fees paid by the transaction, if it was committed
+    pub fees: Option<FeeSummary>,
+    pub logs: Vec<Log>,
+    /// Values returned by every instruction of the manifest
+    pub outputs: Vec<ReturnValue>,
+    pub new_entities: NewEntities,
+    /// Changes of the balances of the account making the call
+    pub balance_changes: Vec<BalanceChange>,
+    /// Manifest run by the transaction, when requested with
+    /// [`ManifestCall::output_manifest`](crate::manifest_call::ManifestCall::output_manifest)
+    pub manifest: Option<String>,
+    /// Full output of the transaction
+    pub stdout: String,
+}
+
+impl Receipt {
+    /// Parses a transaction receipt
+    ///
+    /// # Arguments
+    /// * `stdout` - output of the `resim run` command
+    pub fn from_stdout(stdout: &str) -> Receipt {
+        lazy_static! {
+            static ref STATUS_RE: Regex = Regex::new(r"Transaction Status: (.*)").unwrap();
+            static ref ITEM_RE: Regex = Regex::new(r"^[├└]─ (.*)$").unwrap();
+            static ref LOG_RE: Regex =
+                Regex::new(r"^\[(ERROR|WARN|INFO|DEBUG|TRACE) *\] ?(.*)$").unwrap();
+            static ref ENTITY_RE: Regex =
+                Regex::new(r"^(Package|Component|Resource): (\w*)").unwrap();
+        }
+
+        let clean_stdout = strip_colors(stdout);
+        let status = match STATUS_RE.captures(&clean_stdout) {
+            None => TransactionStatus::Unknown,
+            Some(captures) => {
+                let status = captures[1].trim();
+                if status.starts_with("COMMITTED SUCCESS") {
+                    TransactionStatus::Success
+                } else if let Some(error) = status.strip_prefix("COMMITTED FAILURE:") {
+                    TransactionStatus::Failure(error.trim().to_string())
+                } else if let Some(reason) = status.strip_prefix("REJECTED:") {
+                    TransactionStatus::Rejection(reason.trim().to_string())
+                } else {
+                    TransactionStatus::Unknown
+                }
+            }
+        };
+
+        let mut logs = vec![];
+        let mut new_entities = NewEntities::default();
+        let mut section = "";
+        for line in clean_stdout.lines() {
+            if line.starts_with("Logs:") {
+                section = "logs";
+                continue;
+            }
+            if line.starts_with("New Entities:") {
+                section = "entities";
+                continue;
+            }
+
+            let item = match ITEM_RE.captures(line) {
+                None => {
+                    section = "";
+                    continue;
+                }
+                Some(captures) => captures[1].to_string(),
+            };
+            match section {
+                "logs" => {
+                    if let Some(log) = LOG_RE.captures(&item) {
+                        logs.push(Log {
+                            level: Level::from_label(&log[1]),
+                            message: log[2].to_string(),
+                        });
+                    }
+                }
+                "entities" => {
+                    if let Some(entity) = ENTITY_RE.captures(&item) {
+                        let address = entity[2].to_string();
+                        match &entity[1] {
+                            "Package" => new_entities.packages.push(address),
+                            "Component" => new_entities.components.push(address),
+                            _ => new_entities.resources.push(address),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Receipt {
+            status,
+            fees: FeeSummary::from_receipt(stdout),
+            logs,
+            outputs: ReturnValue::from_receipt(stdout),
+            new_entities,
+            balance_changes: vec![],
+            manifest: None,
+            stdout: stdout.to_string(),
+        }
+    }
+
+    /// Returns whether the transaction was committed and succeeded
+    pub fn is_success(&self) -> bool {
+        self.status == TransactionStatus::Success
+    }
+
+    /// Returns the change of the amount of a resource owned by the account making the call
+    ///
+    /// # Arguments
+    /// * `resource_address` - address of the resource
+    pub fn balance_change(&self, resource_address: &str) -> Decimal {
+        self.balance_changes
+            .iter()
+            .find(|balance_change| balance_change.resource == resource_address)
+            .map_or_else(Decimal::zero, |balance_change| balance_change.change)
+    }
+}
+
+impl Level {
+    fn from_label(label: &str) -> Level {
+        match label {
+            "ERROR" => Level::Error,
+            "WARN" => Level::Warn,
+            "INFO" => Level::Info,
+            "DEBUG" => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+}
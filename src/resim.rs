@@ -0,0 +1,338 @@
+//! Ledger backend driving the resim binary
+
+use crate::backend::{AccountInfo, Backend, ResourceBalance, ResourceInfo};
+use crate::error::SqrtError;
+use crate::return_value::{strip_colors, ReturnValue};
+use crate::utils::{copy_dir, new_directory, try_run_command};
+use lazy_static::lazy_static;
+use regex::Regex;
+use scrypto::prelude::{Decimal, Instant, UtcDateTime};
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Backend running every operation through the resim binary, which should be on the PATH
+///
+/// Every ResimBackend uses its own resim data directory, which is deleted when the backend is dropped, so that
+/// several backends can be used in parallel.
+pub struct ResimBackend {
+    data_dir: PathBuf,
+}
+
+impl ResimBackend {
+    /// Returns a new ResimBackend with its own data directory
+    pub fn new() -> ResimBackend {
+        let data_dir = new_directory("sqrt");
+        fs::create_dir_all(&data_dir).expect("Could not create the resim data directory");
+
+        ResimBackend { data_dir }
+    }
+
+    /// Returns the data directory used by resim
+    pub fn data_dir(&self) -> &PathBuf {
+        &self.data_dir
+    }
+
+    fn resim(&self) -> Command {
+        let mut command = Command::new("resim");
+        command.env("DATA_DIR", &self.data_dir);
+        command
+    }
+
+    fn generate_owner_badge(&self) -> Result<String, SqrtError> {
+        let output = try_run_command(self.resim().arg("new-simple-badge"), false)?;
+
+        lazy_static! {
+            static ref NFADDRESS_RE: Regex = Regex::new(r#"NonFungibleGlobalId: (.*)"#).unwrap();
+        }
+
+        capture(&NFADDRESS_RE, &output)
+    }
+}
+
+impl Backend for ResimBackend {
+    fn reset(&mut self) -> Result<(), SqrtError> {
+        try_run_command(self.resim().arg("reset"), false)?;
+        Ok(())
+    }
+
+    fn new_account(&mut self) -> Result<AccountInfo, SqrtError> {
+        let account_command = try_run_command(self.resim().arg("new-account"), false)?;
+        let owner_badge = self.generate_owner_badge()?;
+
+        lazy_static! {
+            static ref ADDRESS_RE: Regex = Regex::new(r"Account component address: (\w*)").unwrap();
+            static ref PRIVATE_KEY_RE: Regex = Regex::new(r"Private key: (\w*)").unwrap();
+        }
+
+        Ok(AccountInfo {
+            address: capture(&ADDRESS_RE, &account_command)?,
+            private_key: capture(&PRIVATE_KEY_RE, &account_command)?,
+            owner_badge,
+        })
+    }
+
+    fn new_token_fixed(&mut self, initial_supply: Decimal) -> Result<String, SqrtError> {
+        let output = try_run_command(
+            self.resim()
+                .arg("new-token-fixed")
+                .arg(initial_supply.to_string()),
+            false,
+        )?;
+
+        lazy_static! {
+            static ref ADDRESS_RE: Regex = Regex::new(r#"Resource: (\w*)"#).unwrap();
+        }
+
+        capture(&ADDRESS_RE, &output)
+    }
+
+    fn new_token_mutable(&mut self, minter_badge: &str) -> Result<String, SqrtError> {
+        let output = try_run_command(
+            self.resim().arg("new-token-mutable").arg(minter_badge),
+            false,
+        )?;
+
+        lazy_static! {
+            static ref ADDRESS_RE: Regex = Regex::new(r#"Resource: (\w*)"#).unwrap();
+        }
+
+        capture(&ADDRESS_RE, &output)
+    }
+
+    fn publish_package(&mut self, path: &str, owner_badge: &str) -> Result<String, SqrtError> {
+        lazy_static! {
+            static ref PACKAGE_RE: Regex = Regex::new(r"Success! New Package: (\w*)").unwrap();
+        }
+
+        let package_output = try_run_command(
+            self.resim()
+                .arg("publish")
+                .arg(path)
+                .arg("--owner-badge")
+                .arg(owner_badge),
+            false,
+        )?;
+
+        capture(&PACKAGE_RE, &package_output)
+    }
+
+    fn run_manifest(
+        &mut self,
+        manifest_path: &str,
+        env_bindings: &[(String, String)],
+        signers: &[AccountInfo],
+    ) -> Result<(String, String), SqrtError> {
+        let mut command = self.resim();
+        command
+            .arg("run")
+            .arg(manifest_path)
+            .envs(env_bindings.iter().cloned());
+        if !signers.is_empty() {
+            let keys: Vec<&str> = signers
+                .iter()
+                .map(|signer| signer.private_key.as_str())
+                .collect();
+            command.arg("--signing-keys").arg(keys.join(","));
+        }
+
+        try_run_command(&mut command, true)
+    }
+
+    fn resources(&mut self) -> Result<Vec<ResourceInfo>, SqrtError> {
+        let output = try_run_command(self.resim().arg("show-ledger"), false)?;
+
+        lazy_static! {
+            static ref RESOURCES_RE: Regex = Regex::new(r#"resource_(\w*)"#).unwrap();
+            static ref NAME_RE: Regex = Regex::new(r#"name: (.*)"#).unwrap();
+            static ref FUNGIBLE_RE: Regex = Regex::new(r#"Resource Type: Fungible"#).unwrap();
+        }
+
+        let mut resources = vec![];
+        for resource in RESOURCES_RE.captures_iter(&output.0) {
+            let address = format!("{}{}", "resource_", &resource[1]);
+            let output_show = try_run_command(self.resim().arg("show").arg(&address), false)?;
+
+            resources.push(ResourceInfo {
+                address,
+                name: NAME_RE
+                    .captures(&output_show.0)
+                    .map(|catch_name| String::from(&catch_name[1])),
+                is_fungible: FUNGIBLE_RE.is_match(&output_show.0),
+            });
+        }
+
+        Ok(resources)
+    }
+
+    fn metadata(&mut self, address: &str) -> Result<HashMap<String, String>, SqrtError> {
+        let output = try_run_command(self.resim().arg("show").arg(address), false)?;
+
+        lazy_static! {
+            static ref ENTRY_RE: Regex = Regex::new(r"^[├└]─ ([^:]*): (.*)$").unwrap();
+        }
+
+        // The entries are listed right after the number of fields of the metadata
+        let output = strip_colors(&output.0);
+        let metadata = output
+            .lines()
+            .skip_while(|line| !line.starts_with("Metadata:"))
+            .skip(1)
+            .map_while(|line| ENTRY_RE.captures(line))
+            .map(|entry| (entry[1].to_string(), entry[2].to_string()))
+            .collect();
+
+        Ok(metadata)
+    }
+
+    fn balances(&mut self, address: &str) -> Result<Vec<ResourceBalance>, SqrtError> {
+        let account_resources = try_run_command(self.resim().arg("show").arg(address), false)?;
+
+        lazy_static! {
+            static ref RESOURCE_RE: Regex =
+                Regex::new(r#"amount: ([\d.]*), resource address: (\w*)"#).unwrap();
+            static ref NON_FUNGIBLE_RE: Regex =
+                Regex::new(r#"NonFungibleLocalId\("(.*)"\), immutable_data"#).unwrap();
+        }
+
+        let mut balances: Vec<ResourceBalance> = vec![];
+        for line in account_resources.0.lines() {
+            if let Some(resource) = RESOURCE_RE.captures(line) {
+                balances.push(ResourceBalance {
+                    address: String::from(&resource[2]),
+                    amount: Decimal::from(&resource[1]),
+                    non_fungible_ids: vec![],
+                });
+            } else if let Some(nf_resource) = NON_FUNGIBLE_RE.captures(line) {
+                if let Some(balance) = balances.last_mut() {
+                    balance.non_fungible_ids.push(nf_resource[1].to_string());
+                }
+            }
+        }
+
+        Ok(balances)
+    }
+
+    fn component_fields(&mut self, address: &str) -> Result<Vec<ReturnValue>, SqrtError> {
+        let output = try_run_command(self.resim().arg("show").arg(address), false)?;
+
+        lazy_static! {
+            static ref STATE_RE: Regex = Regex::new(r#"State: (.*)"#).unwrap();
+        }
+
+        let output = (strip_colors(&output.0), output.1);
+        let state = capture(&STATE_RE, &output)?;
+        Ok(match ReturnValue::parse(&state) {
+            ReturnValue::Tuple(fields) => fields,
+            value => vec![value],
+        })
+    }
+
+    fn export_abi(
+        &mut self,
+        package_address: &str,
+        blueprint_name: &str,
+    ) -> Result<String, SqrtError> {
+        let output = try_run_command(
+            self.resim()
+                .arg("export-abi")
+                .arg(package_address)
+                .arg(blueprint_name),
+            false,
+        )?;
+        Ok(output.0)
+    }
+
+    fn set_default_account(
+        &mut self,
+        address: &str,
+        private_key: &str,
+        owner_badge: &str,
+    ) -> Result<(), SqrtError> {
+        try_run_command(
+            self.resim()
+                .arg("set-default-account")
+                .arg(address)
+                .arg(private_key)
+                .arg(owner_badge),
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn set_current_epoch(&mut self, epoch: u64) -> Result<(), SqrtError> {
+        try_run_command(
+            self.resim().arg("set-current-epoch").arg(epoch.to_string()),
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn set_current_time(&mut self, time: Instant) -> Result<(), SqrtError> {
+        let utc_time = UtcDateTime::from_instant(&time).unwrap();
+
+        try_run_command(
+            self.resim()
+                .arg("set-current-time")
+                .arg(format!("{}", utc_time)),
+            false,
+        )?;
+        Ok(())
+    }
+
+    fn snapshot(&mut self) -> Box<dyn Any + Send + Sync> {
+        let snapshot = DataDirSnapshot {
+            path: new_directory("sqrt-snapshot"),
+        };
+        copy_dir(&self.data_dir, &snapshot.path);
+        Box::new(snapshot)
+    }
+
+    fn restore(&mut self, snapshot: &dyn Any) {
+        let snapshot = snapshot
+            .downcast_ref::<DataDirSnapshot>()
+            .expect("The snapshot was not taken by a ResimBackend");
+        let _ = fs::remove_dir_all(&self.data_dir);
+        copy_dir(&snapshot.path, &self.data_dir);
+    }
+}
+
+/// Returns the first group captured by a regex in the stdout of a command, or an error with the output of the command
+///
+/// # Arguments
+/// * `regex` - regex with one capture group
+/// * `output` - stdout and stderr of the command
+fn capture(regex: &Regex, output: &(String, String)) -> Result<String, SqrtError> {
+    match regex.captures(&output.0) {
+        Some(captures) => Ok(captures[1].to_string()),
+        None => Err(SqrtError::ResimFailure {
+            stdout: output.0.clone(),
+            stderr: output.1.clone(),
+        }),
+    }
+}
+
+/// Copy of a resim data directory, deleted when dropped
+struct DataDirSnapshot {
+    path: PathBuf,
+}
+
+impl Drop for DataDirSnapshot {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+impl Default for ResimBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ResimBackend {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.data_dir);
+    }
+}
@@ -0,0 +1,432 @@
+//! Parses and decodes the values returned by the instructions of a transaction
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use scrypto::prelude::{Decimal, PreciseDecimal};
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+#[derive(Debug, Clone, PartialEq)]
+/// Value returned by an instruction of a transaction
+pub enum ReturnValue {
+    Unit,
+    Bool(bool),
+    I8(i8),
+    I16(i16),
+    I32(i32),
+    I64(i64),
+    I128(i128),
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    U128(u128),
+    String(String),
+    Decimal(Decimal),
+    PreciseDecimal(PreciseDecimal),
+    /// Address of a package as a bech32 [String]
+    PackageAddress(String),
+    /// Address of a component as a bech32 [String]
+    ComponentAddress(String),
+    /// Address of a resource as a bech32 [String]
+    ResourceAddress(String),
+    NonFungibleLocalId(String),
+    /// Bucket returned by an instruction. As resim only displays the identifier of returned buckets, the [String]
+    /// contains this identifier; the content of the bucket ends up in the caller's account.
+    Bucket(String),
+    /// Proof returned by an instruction. The [String] contains the identifier of the proof.
+    Proof(String),
+    /// Vault owned by a component. The [String] contains the identifier of the vault.
+    Vault(String),
+    Tuple(Vec<ReturnValue>),
+    Array(Vec<ReturnValue>),
+    /// Enum value. The [String] is the variant and the [Vec] contains its fields
+    Enum(String, Vec<ReturnValue>),
+    /// Any other value, as displayed by resim
+    Other(String),
+}
+
+impl ReturnValue {
+    /// Parses the "Instruction Outputs" section of a transaction receipt
+    ///
+    /// # Arguments
+    /// * `stdout` - output of the `resim run` command
+    pub fn from_receipt(stdout: &str) -> Vec<ReturnValue> {
+        lazy_static! {
+            static ref OUTPUT_RE: Regex = Regex::new(r"^[├└]─ (.*)$").unwrap();
+        }
+
+        let stdout = strip_colors(stdout);
+        let mut outputs = vec![];
+        let mut in_outputs = false;
+        for line in stdout.lines() {
+            if line.starts_with("Instruction Outputs:") {
+                in_outputs = true;
+                continue;
+            }
+            if !in_outputs {
+                continue;
+            }
+            match OUTPUT_RE.captures(line) {
+                None => break,
+                Some(output) => outputs.push(Self::parse(&output[1])),
+            }
+        }
+
+        outputs
+    }
+
+    /// Parses a value as displayed by resim
+    ///
+    /// # Arguments
+    /// * `value` - value to parse
+    pub fn parse(value: &str) -> ReturnValue {
+        let mut parser = ValueParser {
+            chars: value.trim().chars().peekable(),
+        };
+        match parser.parse_value() {
+            Some(parsed) if parser.is_finished() => parsed,
+            _ => ReturnValue::Other(value.trim().to_string()),
+        }
+    }
+
+    /// Decodes the value into a Rust type and panics if the value has another type
+    pub fn decode<T: FromReturnValue>(&self) -> T {
+        match T::from_return_value(self) {
+            None => panic!(
+                "Could not decode returned value {:?} into a {}",
+                self,
+                std::any::type_name::<T>()
+            ),
+            Some(value) => value,
+        }
+    }
+}
+
+/// Trait implemented by Rust types that can be decoded from a [`ReturnValue`]
+pub trait FromReturnValue: Sized {
+    /// Returns the decoded value or `None` if the [`ReturnValue`] has another type
+    fn from_return_value(value: &ReturnValue) -> Option<Self>;
+}
+
+impl FromReturnValue for ReturnValue {
+    fn from_return_value(value: &ReturnValue) -> Option<Self> {
+        Some(value.clone())
+    }
+}
+
+impl FromReturnValue for () {
+    fn from_return_value(value: &ReturnValue) -> Option<Self> {
+        match value {
+            ReturnValue::Unit => Some(()),
+            ReturnValue::Tuple(elements) if elements.is_empty() => Some(()),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_return_value {
+    ($type:ty, $variant:ident) => {
+        impl FromReturnValue for $type {
+            fn from_return_value(value: &ReturnValue) -> Option<Self> {
+                match value {
+                    ReturnValue::$variant(inner) => Some(inner.clone()),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_from_return_value!(bool, Bool);
+impl_from_return_value!(i8, I8);
+impl_from_return_value!(i16, I16);
+impl_from_return_value!(i32, I32);
+impl_from_return_value!(i64, I64);
+impl_from_return_value!(i128, I128);
+impl_from_return_value!(u8, U8);
+impl_from_return_value!(u16, U16);
+impl_from_return_value!(u32, U32);
+impl_from_return_value!(u64, U64);
+impl_from_return_value!(u128, U128);
+impl_from_return_value!(Decimal, Decimal);
+impl_from_return_value!(PreciseDecimal, PreciseDecimal);
+
+impl FromReturnValue for String {
+    /// Strings, addresses, non fungible ids, buckets, proofs and vaults are all decoded as a [String]
+    fn from_return_value(value: &ReturnValue) -> Option<Self> {
+        match value {
+            ReturnValue::String(inner)
+            | ReturnValue::PackageAddress(inner)
+            | ReturnValue::ComponentAddress(inner)
+            | ReturnValue::ResourceAddress(inner)
+            | ReturnValue::NonFungibleLocalId(inner)
+            | ReturnValue::Bucket(inner)
+            | ReturnValue::Proof(inner)
+            | ReturnValue::Vault(inner) => Some(inner.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromReturnValue> FromReturnValue for Vec<T> {
+    fn from_return_value(value: &ReturnValue) -> Option<Self> {
+        match value {
+            ReturnValue::Array(elements) => elements.iter().map(T::from_return_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FromReturnValue> FromReturnValue for Option<T> {
+    fn from_return_value(value: &ReturnValue) -> Option<Self> {
+        match value {
+            ReturnValue::Enum(variant, fields) => match (variant.as_str(), fields.as_slice()) {
+                ("None", []) => Some(None),
+                ("Some", [inner]) => T::from_return_value(inner).map(Some),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_return_value_tuple {
+    ($len:expr, $($type:ident $index:tt),+) => {
+        impl<$($type: FromReturnValue),+> FromReturnValue for ($($type,)+) {
+            fn from_return_value(value: &ReturnValue) -> Option<Self> {
+                match value {
+                    ReturnValue::Tuple(elements) if elements.len() == $len => {
+                        Some(($($type::from_return_value(&elements[$index])?,)+))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_from_return_value_tuple!(1, A 0);
+impl_from_return_value_tuple!(2, A 0, B 1);
+impl_from_return_value_tuple!(3, A 0, B 1, C 2);
+impl_from_return_value_tuple!(4, A 0, B 1, C 2, D 3);
+impl_from_return_value_tuple!(5, A 0, B 1, C 2, D 3, E 4);
+
+/// Removes the terminal color codes from resim's output
+pub(crate) fn strip_colors(output: &str) -> String {
+    lazy_static! {
+        static ref COLOR_RE: Regex = Regex::new("\x1b\\[[0-9;]*m").unwrap();
+    }
+    COLOR_RE.replace_all(output, "").into_owned()
+}
+
+struct ValueParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> ValueParser<'a> {
+    fn is_finished(&mut self) -> bool {
+        self.skip_whitespaces();
+        self.chars.peek().is_none()
+    }
+
+    fn parse_value(&mut self) -> Option<ReturnValue> {
+        self.skip_whitespaces();
+        match *self.chars.peek()? {
+            '"' => self.parse_string().map(ReturnValue::String),
+            '(' => {
+                let elements = self.parse_elements()?;
+                if elements.is_empty() {
+                    Some(ReturnValue::Unit)
+                } else {
+                    Some(ReturnValue::Tuple(elements))
+                }
+            }
+            c if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            c if c.is_alphabetic() => self.parse_named_value(),
+            _ => None,
+        }
+    }
+
+    fn parse_named_value(&mut self) -> Option<ReturnValue> {
+        let name = self.parse_identifier();
+        self.skip_generics()?;
+        self.skip_whitespaces();
+
+        if self.chars.peek() != Some(&'(') {
+            return match name.as_str() {
+                "true" => Some(ReturnValue::Bool(true)),
+                "false" => Some(ReturnValue::Bool(false)),
+                _ => Some(ReturnValue::Enum(name, vec![])),
+            };
+        }
+
+        let mut elements = self.parse_elements()?;
+        let value = match name.as_str() {
+            "Tuple" if elements.is_empty() => ReturnValue::Unit,
+            "Tuple" => ReturnValue::Tuple(elements),
+            "Array" | "Vec" | "Set" | "Map" => ReturnValue::Array(elements),
+            "Enum" => {
+                if elements.is_empty() {
+                    return None;
+                }
+                let variant = match elements.remove(0) {
+                    ReturnValue::String(variant) => variant,
+                    ReturnValue::U8(variant) => variant.to_string(),
+                    _ => return None,
+                };
+                ReturnValue::Enum(variant, elements)
+            }
+            "Decimal" => {
+                ReturnValue::Decimal(Decimal::from_str(&Self::single_string(elements)?).ok()?)
+            }
+            "PreciseDecimal" => ReturnValue::PreciseDecimal(
+                PreciseDecimal::from_str(&Self::single_string(elements)?).ok()?,
+            ),
+            "PackageAddress" => ReturnValue::PackageAddress(Self::single_string(elements)?),
+            "ComponentAddress" => ReturnValue::ComponentAddress(Self::single_string(elements)?),
+            "ResourceAddress" => ReturnValue::ResourceAddress(Self::single_string(elements)?),
+            "NonFungibleLocalId" => ReturnValue::NonFungibleLocalId(Self::single_string(elements)?),
+            "Bucket" => ReturnValue::Bucket(Self::single_identifier(elements)?),
+            "Proof" => ReturnValue::Proof(Self::single_identifier(elements)?),
+            "Own" => {
+                let own = Self::single_string(elements)?;
+                if own.starts_with("Bucket") {
+                    ReturnValue::Bucket(own)
+                } else if own.starts_with("Proof") {
+                    ReturnValue::Proof(own)
+                } else if own.starts_with("Vault") {
+                    ReturnValue::Vault(own)
+                } else {
+                    ReturnValue::Other(format!("Own(\"{}\")", own))
+                }
+            }
+            _ => ReturnValue::Enum(name, elements),
+        };
+
+        Some(value)
+    }
+
+    fn parse_elements(&mut self) -> Option<Vec<ReturnValue>> {
+        if self.chars.next()? != '(' {
+            return None;
+        }
+
+        let mut elements = vec![];
+        loop {
+            self.skip_whitespaces();
+            if self.chars.peek() == Some(&')') {
+                self.chars.next();
+                return Some(elements);
+            }
+            elements.push(self.parse_value()?);
+            self.skip_whitespaces();
+            match self.chars.next()? {
+                ',' => {}
+                ')' => return Some(elements),
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        self.chars.next();
+        let mut string = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(string),
+                '\\' => match self.chars.next()? {
+                    'n' => string.push('\n'),
+                    't' => string.push('\t'),
+                    'r' => string.push('\r'),
+                    c => string.push(c),
+                },
+                c => string.push(c),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<ReturnValue> {
+        let mut digits = String::new();
+        if self.chars.peek() == Some(&'-') {
+            digits.push('-');
+            self.chars.next();
+        }
+        while let Some(c) = self.chars.peek() {
+            if !c.is_ascii_digit() {
+                break;
+            }
+            digits.push(*c);
+            self.chars.next();
+        }
+        let value = match self.parse_identifier().as_str() {
+            "i8" => ReturnValue::I8(digits.parse().ok()?),
+            "i16" => ReturnValue::I16(digits.parse().ok()?),
+            "i32" | "" => ReturnValue::I32(digits.parse().ok()?),
+            "i64" => ReturnValue::I64(digits.parse().ok()?),
+            "i128" => ReturnValue::I128(digits.parse().ok()?),
+            "u8" => ReturnValue::U8(digits.parse().ok()?),
+            "u16" => ReturnValue::U16(digits.parse().ok()?),
+            "u32" => ReturnValue::U32(digits.parse().ok()?),
+            "u64" => ReturnValue::U64(digits.parse().ok()?),
+            "u128" => ReturnValue::U128(digits.parse().ok()?),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn parse_identifier(&mut self) -> String {
+        let mut identifier = String::new();
+        while let Some(c) = self.chars.peek() {
+            if !c.is_alphanumeric() && *c != '_' && *c != ':' {
+                break;
+            }
+            identifier.push(*c);
+            self.chars.next();
+        }
+        identifier
+    }
+
+    fn skip_generics(&mut self) -> Option<()> {
+        if self.chars.peek() != Some(&'<') {
+            return Some(());
+        }
+        let mut depth = 0;
+        loop {
+            match self.chars.next()? {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while let Some(c) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+    }
+
+    fn single_string(elements: Vec<ReturnValue>) -> Option<String> {
+        match elements.as_slice() {
+            [ReturnValue::String(string)] => Some(string.clone()),
+            _ => None,
+        }
+    }
+
+    fn single_identifier(elements: Vec<ReturnValue>) -> Option<String> {
+        match elements.as_slice() {
+            [ReturnValue::String(id)] => Some(id.clone()),
+            [ReturnValue::U32(id)] => Some(id.to_string()),
+            _ => None,
+        }
+    }
+}
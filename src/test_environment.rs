@@ -1,45 +1,91 @@
 //! Environment for a test
 
+use crate::abi::BlueprintAbi;
 use crate::account::Account;
-use crate::blueprint::{AdminBadge, Blueprint};
+use crate::backend::{AccountInfo, Backend};
+use crate::blueprint::{AdminBadge, Blueprint, ReturnedItem};
 use crate::component::Component;
+use crate::component_state::ComponentState;
+use crate::cost_tracking::CostTracking;
+use crate::error::SqrtError;
+use crate::fee::{FeeLock, FeePayer};
+use crate::instructions::Instruction;
 use crate::manifest::Manifest;
 use crate::manifest_call::ManifestCall;
-use crate::method::{Arg, Method};
+use crate::method::{AddressBook, Arg, Method};
+use crate::non_fungible::{LocalId, NonFungibleData};
 use crate::package::Package;
+use crate::receipt::{Receipt, TransactionStatus};
+use crate::resim::ResimBackend;
 use crate::resource_manager::ResourceManager;
+use crate::return_value::ReturnValue;
+use crate::transaction_builder::TransactionBuilder;
 use crate::transfer::Deposit;
 use crate::utils::{
-    create_dir, generated_manifest_exists, run_command, run_manifest, write_manifest,
+    create_dir, custom_manifest_path, generated_manifest_path, run_manifest, write_manifest,
+    TempDirectory,
 };
-use lazy_static::lazy_static;
-use regex::Regex;
-use scrypto::prelude::{Decimal, Instant, UtcDateTime};
+use scrypto::prelude::{Decimal, Instant};
+use std::any::Any;
 use std::collections::HashMap;
-use std::process::Command;
+use std::path::Path;
+use std::sync::Arc;
 
 pub struct TestEnvironment {
+    backend: Box<dyn Backend>,
     accounts: HashMap<String, Account>,
-    packages: HashMap<String, Package>,
+    packages: HashMap<String, Arc<Package>>,
     components: HashMap<String, Component>,
     resource_manager: ResourceManager,
     current_account: String,
     current_package: Option<String>,
     current_component: Option<String>,
+    /// ABIs of the blueprints of called components, by package address and blueprint name
+    abis: HashMap<(String, String), BlueprintAbi>,
+    fee_lock: FeeLock,
+    cost_tracking: CostTracking,
+    /// Directory of the manifests generated by the environment, so that environments never share them
+    manifests_dir: TempDirectory,
 }
 
+/// State of a [`TestEnvironment`] saved by [`TestEnvironment::snapshot`]
+///
+/// A Snapshot is `Send` and `Sync`, so that a fixture can be built once in a `static` and restored by several tests.
+pub struct Snapshot {
+    ledger: Box<dyn Any + Send + Sync>,
+    accounts: HashMap<String, Account>,
+    packages: HashMap<String, Arc<Package>>,
+    components: HashMap<String, Component>,
+    resource_manager: ResourceManager,
+    current_account: String,
+    current_package: Option<String>,
+    current_component: Option<String>,
+    abis: HashMap<(String, String), BlueprintAbi>,
+    fee_lock: FeeLock,
+    cost_tracking: CostTracking,
+}
+
+/// Name of the manifest calling a function, index of the output of the call and bindings of the manifest
+type FunctionBindings = (String, usize, Vec<(String, String)>);
+
 impl TestEnvironment {
-    /// Returns a new TestEnvironment
+    /// Returns a new TestEnvironment running its transactions with resim
     pub fn new() -> TestEnvironment {
-        Self::reset();
+        Self::with_backend(Box::new(ResimBackend::new()))
+    }
 
-        let mut default_account = Account::new();
-        let mut resource_manager = ResourceManager::new();
-        resource_manager.submit_owner_badge(&mut default_account, "default");
+    /// Returns a new TestEnvironment running its transactions on the given backend
+    ///
+    /// # Arguments
+    /// * `backend` - ledger backend to use
+    pub fn with_backend(mut backend: Box<dyn Backend>) -> TestEnvironment {
+        let (default_account, resource_manager) =
+            Self::initialize(backend.as_mut()).unwrap_or_else(|error| panic!("{}", error));
         let mut accounts = HashMap::new();
         accounts.insert(String::from("default"), default_account);
 
         TestEnvironment {
+            backend,
             accounts,
             packages: HashMap::new(),
             components: HashMap::new(),
@@ -47,6 +93,10 @@ impl TestEnvironment {
             current_account: String::from("default"),
             current_package: None,
             current_component: None,
+            abis: HashMap::new(),
+            fee_lock: FeeLock::default(),
+            cost_tracking: CostTracking::default(),
+            manifests_dir: TempDirectory::new("sqrt-manifests"),
         }
     }
 
@@ -55,15 +105,27 @@ impl TestEnvironment {
     /// # Arguments
     /// * `name` - name associated to the account
     pub fn create_account(&mut self, name: &str) -> &str {
+        self.try_create_account(name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new account with a given name, or returns an error if the name is already used
+    ///
+    /// # Arguments
+    /// * `name` - name associated to the account
+    pub fn try_create_account(&mut self, name: &str) -> Result<&str, SqrtError> {
         let real_name = String::from(name).to_lowercase();
         if self.accounts.contains_key(&real_name) {
-            panic!("An account with this name already exists");
+            Err(SqrtError::NameConflict(real_name))
         } else {
-            let mut new_account = Account::new();
-            self.resource_manager
-                .submit_owner_badge(&mut new_account, &real_name);
+            let mut new_account = Account::new(self.backend.as_mut())?;
+            self.resource_manager.submit_owner_badge(
+                &mut new_account,
+                &real_name,
+                self.backend.as_mut(),
+            )?;
             self.accounts.insert(real_name.clone(), new_account);
-            self.accounts.get(&real_name).unwrap().address()
+            Ok(self.accounts.get(&real_name).unwrap().address())
         }
     }
 
@@ -73,26 +135,29 @@ impl TestEnvironment {
     /// * `name` - name associated to the token
     /// * `initial_supply` - initial supply for the token
     pub fn create_fixed_supply_token(&mut self, name: &str, initial_supply: Decimal) {
+        self.try_create_fixed_supply_token(name, initial_supply)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new token with fixed supply and with a given name, or returns an error if the name is already used
+    ///
+    /// # Arguments
+    /// * `name` - name associated to the token
+    /// * `initial_supply` - initial supply for the token
+    pub fn try_create_fixed_supply_token(
+        &mut self,
+        name: &str,
+        initial_supply: Decimal,
+    ) -> Result<(), SqrtError> {
         let name = String::from(name);
         if self.resource_manager.exists(&name) {
-            panic!("A token with same name already exists!")
+            Err(SqrtError::NameConflict(name))
         } else {
-            let output = run_command(
-                Command::new("resim")
-                    .arg("new-token-fixed")
-                    .arg(initial_supply.to_string()),
-                false,
-            );
-
-            lazy_static! {
-                static ref ADDRESS_RE: Regex = Regex::new(r#"Resource: (\w*)"#).unwrap();
-            }
-
-            let resource_address = String::from(&ADDRESS_RE.captures(&output.0).unwrap()[1]);
+            let resource_address = self.backend.new_token_fixed(initial_supply)?;
 
             self.resource_manager
                 .add_resource(&name, resource_address, true);
-            self.update_current_account();
+            self.update_current_account()
         }
     }
 
@@ -102,29 +167,242 @@ impl TestEnvironment {
     /// * `name` - name associated to the token
     /// * `minter_badge` - name associated to the badge that will be used to mint the token
     pub fn create_mintable_token(&mut self, name: &str, minter_badge: &str) {
+        self.try_create_mintable_token(name, minter_badge)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a new token with mutable supply and with a given name, or returns an error if the name is already used
+    /// or the minter badge does not exist
+    ///
+    /// # Arguments
+    /// * `name` - name associated to the token
+    /// * `minter_badge` - name associated to the badge that will be used to mint the token
+    pub fn try_create_mintable_token(
+        &mut self,
+        name: &str,
+        minter_badge: &str,
+    ) -> Result<(), SqrtError> {
         let name = String::from(name);
         if self.resource_manager.exists(&name) {
-            panic!("A token with same name already exists!")
+            Err(SqrtError::NameConflict(name))
         } else {
-            let minter_badge = self.resource_manager.get_address(minter_badge);
+            let minter_badge = self.try_get_resource(minter_badge)?.clone();
+            let resource_address = self.backend.new_token_mutable(&minter_badge)?;
+
+            self.resource_manager
+                .set_minter(&resource_address, &minter_badge);
+            self.resource_manager
+                .add_resource(&name, resource_address, true);
+            self.update_current_account()
+        }
+    }
+
+    /// Creates a new non fungible resource with a given name and initial supply, deposited in the current account
+    ///
+    /// The resource is registered as non fungible, so that its ids can be used in
+    /// [`Arg::NonFungibleBucketArg`] and [`Arg::NonFungibleProofArg`] right away.
+    /// # Arguments
+    /// * `name` - name associated to the resource
+    /// * `ids_and_data` - ids of the initial supply with their data, all the ids having the same type
+    /// * `minter_badge` - name associated to the badge allowed to mint, burn and update the data of the resource
+    pub fn create_non_fungible_resource(
+        &mut self,
+        name: &str,
+        ids_and_data: Vec<(LocalId, NonFungibleData)>,
+        minter_badge: Option<&str>,
+    ) {
+        self.try_create_non_fungible_resource(name, ids_and_data, minter_badge)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-            let output = run_command(
-                Command::new("resim")
-                    .arg("new-token-mutable")
-                    .arg(minter_badge),
-                false,
-            );
+    /// Creates a new non fungible resource with a given name and initial supply, or returns an error if the name is
+    /// already used, there are no ids or ids of different types, the minter badge does not exist or the transaction
+    /// failed
+    ///
+    /// No package needs to be published, as the manifest is written in the directory of the environment.
+    /// # Arguments
+    /// * `name` - name associated to the resource
+    /// * `ids_and_data` - ids of the initial supply with their data, all the ids having the same type
+    /// * `minter_badge` - name associated to the badge allowed to mint, burn and update the data of the resource
+    pub fn try_create_non_fungible_resource(
+        &mut self,
+        name: &str,
+        ids_and_data: Vec<(LocalId, NonFungibleData)>,
+        minter_badge: Option<&str>,
+    ) -> Result<(), SqrtError> {
+        let name = String::from(name);
+        if self.resource_manager.exists(&name) {
+            return Err(SqrtError::NameConflict(name));
+        }
 
-            lazy_static! {
-                static ref ADDRESS_RE: Regex = Regex::new(r#"Resource: (\w*)"#).unwrap();
+        let mut env_binding = self.fee_bindings();
+        let caller = self.get_current_account().address().to_string();
+        env_binding.push((Manifest::caller_arg(), caller));
+        let minter_badge_address = match minter_badge {
+            None => None,
+            Some(badge) => Some(self.try_get_resource(badge)?.clone()),
+        };
+
+        let mut manifest = Manifest::with_fee_lock(&self.fee_lock);
+        manifest.lock_bound_fee();
+        manifest.create_non_fungible_resource(
+            &name,
+            &ids_and_data,
+            minter_badge_address.as_deref(),
+            self,
+        )?;
+        manifest.finish();
+
+        // Manifests are named after the resource, as their content depends on its ids and data
+        let manifest_name = format!("create_{}", name.to_lowercase());
+        let path = self.write_manifest(manifest.build(), &manifest_name);
+        let (_, stdout, stderr) = self.run_manifest(&path, env_binding)?;
+        let receipt = Receipt::from_stdout(&stdout);
+        if receipt.status == TransactionStatus::Unknown {
+            return Err(SqrtError::ResimFailure { stdout, stderr });
+        }
+        match receipt.new_entities.resources.first() {
+            Some(address) if receipt.is_success() => {
+                if let Some(badge_address) = &minter_badge_address {
+                    self.resource_manager.set_minter(address, badge_address);
+                }
+                self.resource_manager
+                    .add_resource(&name, address.clone(), false);
+                self.update_current_account()
             }
+            _ => Err(SqrtError::TransactionFailed(Box::new(receipt))),
+        }
+    }
 
-            let resource_address = String::from(&ADDRESS_RE.captures(&output.0).unwrap()[1]);
+    /// Mints new tokens of a given fungible resource and deposits them in the current account
+    ///
+    /// The current account should own the minter badge of the resource, if it has one.
+    /// # Arguments
+    /// * `token` - name associated to the resource
+    /// * `amount` - amount to mint
+    pub fn mint(&mut self, token: &str, amount: Decimal) {
+        self.try_mint(token, amount)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-            self.resource_manager
-                .add_resource(&name, resource_address, true);
-            self.update_current_account();
+    /// Mints new tokens of a given fungible resource and deposits them in the current account, or returns an error if
+    /// the resource does not exist, is not fungible or the transaction failed
+    ///
+    /// The current account should own the minter badge of the resource, if it has one.
+    /// # Arguments
+    /// * `token` - name associated to the resource
+    /// * `amount` - amount to mint
+    pub fn try_mint(&mut self, token: &str, amount: Decimal) -> Result<(), SqrtError> {
+        let resource_address = self.try_get_resource(token)?.clone();
+        if !self.resource_manager.is_fungible(&resource_address) {
+            return Err(SqrtError::NotFungible(token.to_string()));
+        }
+        let badge_address = self.resource_manager.minter(&resource_address).cloned();
+
+        let mut manifest = Manifest::with_fee_lock(&self.fee_lock);
+        manifest.lock_bound_fee();
+        let badge_arg = badge_address.as_ref().map(|_| Manifest::admin_badge_arg());
+        manifest.mint_fungible(&resource_address, amount, badge_arg.as_deref());
+        manifest.finish();
+
+        self.run_resource_manifest("mint", manifest, badge_address)
+    }
+
+    /// Burns tokens of a given resource owned by the current account
+    ///
+    /// The current account should own the minter badge of the resource, if it has one.
+    /// # Arguments
+    /// * `token` - name associated to the resource
+    /// * `amount` - amount to burn
+    pub fn burn(&mut self, token: &str, amount: Decimal) {
+        self.try_burn(token, amount)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Burns tokens of a given resource owned by the current account, or returns an error if the resource does not
+    /// exist or the transaction failed
+    ///
+    /// The current account should own the minter badge of the resource, if it has one.
+    /// # Arguments
+    /// * `token` - name associated to the resource
+    /// * `amount` - amount to burn
+    pub fn try_burn(&mut self, token: &str, amount: Decimal) -> Result<(), SqrtError> {
+        let resource_address = self.try_get_resource(token)?.clone();
+        let badge_address = self.resource_manager.minter(&resource_address).cloned();
+
+        let mut manifest = Manifest::with_fee_lock(&self.fee_lock);
+        manifest.lock_bound_fee();
+        let badge_arg = badge_address.as_ref().map(|_| Manifest::admin_badge_arg());
+        manifest.burn(&resource_address, amount, badge_arg.as_deref());
+        manifest.finish();
+
+        self.run_resource_manifest("burn", manifest, badge_address)
+    }
+
+    /// Sets a metadata field of a given resource
+    ///
+    /// The current account should own the minter badge of the resource, if it has one.
+    /// # Arguments
+    /// * `token` - name associated to the resource
+    /// * `key` - key of the field
+    /// * `value` - value of the field
+    pub fn set_metadata(&mut self, token: &str, key: &str, value: &str) {
+        self.try_set_metadata(token, key, value)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Sets a metadata field of a given resource, or returns an error if the resource does not exist or the
+    /// transaction failed
+    ///
+    /// The current account should own the minter badge of the resource, if it has one.
+    /// # Arguments
+    /// * `token` - name associated to the resource
+    /// * `key` - key of the field
+    /// * `value` - value of the field
+    pub fn try_set_metadata(
+        &mut self,
+        token: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<(), SqrtError> {
+        let resource_address = self.try_get_resource(token)?.clone();
+        let badge_address = self.resource_manager.minter(&resource_address).cloned();
+
+        let mut manifest = Manifest::with_fee_lock(&self.fee_lock);
+        manifest.lock_bound_fee();
+        let badge_arg = badge_address.as_ref().map(|_| Manifest::admin_badge_arg());
+        manifest.set_metadata(&resource_address, key, value, badge_arg.as_deref());
+        manifest.finish();
+
+        self.run_resource_manifest("set_metadata", manifest, badge_address)
+    }
+
+    /// Writes and runs a manifest managing a resource, then updates the balances of the current account, or returns an
+    /// error if the transaction failed
+    fn run_resource_manifest(
+        &mut self,
+        name: &str,
+        manifest: Manifest,
+        badge_address: Option<String>,
+    ) -> Result<(), SqrtError> {
+        let path = self.write_manifest(manifest.build(), name);
+
+        let mut env_binding = self.fee_bindings();
+        let caller = self.get_current_account().address().to_string();
+        env_binding.push((Manifest::caller_arg(), caller));
+        if let Some(badge_address) = badge_address {
+            env_binding.push((Manifest::admin_badge_arg(), badge_address));
+        }
+
+        let (_, stdout, stderr) = self.run_manifest(&path, env_binding)?;
+        let receipt = Receipt::from_stdout(&stdout);
+        if receipt.status == TransactionStatus::Unknown {
+            return Err(SqrtError::ResimFailure { stdout, stderr });
         }
+        if !receipt.is_success() {
+            return Err(SqrtError::TransactionFailed(Box::new(receipt)));
+        }
+        self.try_update()
     }
 
     /// Publishes a new package to resim and the test environment
@@ -132,114 +410,223 @@ impl TestEnvironment {
     /// # Arguments
     /// * `name` - name associated to the package
     /// * `package` - package to publish
-    pub fn publish_package(&mut self, name: &str, mut package: Package) {
+    pub fn publish_package(&mut self, name: &str, package: Package) {
+        self.try_publish_package(name, package)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Publishes a new package to resim and the test environment, or returns an error if the name is already used or
+    /// the package could not be published
+    ///
+    /// # Arguments
+    /// * `name` - name associated to the package
+    /// * `package` - package to publish
+    pub fn try_publish_package(
+        &mut self,
+        name: &str,
+        mut package: Package,
+    ) -> Result<(), SqrtError> {
         let real_name = String::from(name).to_lowercase();
 
         if !self.packages.contains_key(&real_name) {
-            lazy_static! {
-                static ref PACKAGE_RE: Regex = Regex::new(r"Success! New Package: (\w*)").unwrap();
-            }
+            let owner_badge = self.get_current_account().owner_badge().to_string();
+            let package_address = self.backend.publish_package(package.path(), &owner_badge)?;
 
-            let package_output = run_command(
-                Command::new("resim")
-                    .arg("publish")
-                    .arg(package.path())
-                    .arg("--owner-badge")
-                    .arg(self.get_current_account().owner_badge()),
-                false,
-            );
-
-            let package_address = &PACKAGE_RE.captures(&package_output.0).expect(&format!(
-                "Something went wrong! Maybe the path was incorrect? \n{}",
-                package_output.0
-            ))[1];
-
-            package.set_address(String::from(package_address));
+            package.set_address(package_address);
             create_dir(package.path());
-            self.packages.insert(real_name.clone(), package);
+            self.packages.insert(real_name.clone(), Arc::new(package));
 
             if self.current_package.is_none() {
                 self.set_current_package(name);
             };
+            Ok(())
         } else {
-            panic!("A package with the same name already exists!");
+            Err(SqrtError::NameConflict(real_name))
         }
     }
 
     /// Creates a new Component of a given blueprint
     ///
-    /// When instantiating a new component, newly created resources will be added to the TestEnvironment
+    /// When instantiating a new component, newly created resources will be added to the TestEnvironment. The
+    /// elements returned by the instantiation function are registered as described by
+    /// [`Blueprint::instantiation_return`].
     /// # Arguments
     /// * `name` - name associated to the component
     /// * `blueprint_name` - name of the blueprint
     /// * `args` - value of the arguments needed to instantiate the Component
     pub fn new_component(&mut self, name: &str, blueprint_name: &str, args: Vec<Arg>) {
-        if self.current_package.is_none() {
-            panic!("Please create a package first");
-        }
+        self.try_new_component(name, blueprint_name, args)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-        if self.components.contains_key(name) {
-            panic!("A component with the same name already exists!")
-        }
+    /// Creates a new Component of a given blueprint, or returns an error if the name is already used, the blueprint
+    /// or a resource of the arguments does not exist or the instantiation failed
+    ///
+    /// # Arguments
+    /// * `name` - name associated to the component
+    /// * `blueprint_name` - name of the blueprint
+    /// * `args` - value of the arguments needed to instantiate the Component
+    pub fn try_new_component(
+        &mut self,
+        name: &str,
+        blueprint_name: &str,
+        args: Vec<Arg>,
+    ) -> Result<(), SqrtError> {
+        let package = self.current_package_rc()?;
+        let function_name = match package.get_blueprint(blueprint_name) {
+            Some(blueprint) => blueprint.instantiation_name().to_string(),
+            None => return Err(SqrtError::UnknownBlueprint(blueprint_name.to_string())),
+        };
 
-        let package = self.get_current_package();
+        self.try_new_component_with(name, blueprint_name, &function_name, args)
+    }
 
-        match package.get_blueprint(blueprint_name) {
-            Some(box_blueprint) => {
-                let blueprint = box_blueprint.as_ref();
-                let output = self.instantiate(blueprint, package.path(), package.address(), &args);
+    /// Creates a new Component of a given blueprint with a given function
+    ///
+    /// The elements returned by the function are registered as described by [`Blueprint::function_return`].
+    /// # Arguments
+    /// * `name` - name associated to the component
+    /// * `blueprint_name` - name of the blueprint
+    /// * `function_name` - name of the function instantiating the Component
+    /// * `args` - value of the arguments of the function
+    pub fn new_component_with(
+        &mut self,
+        name: &str,
+        blueprint_name: &str,
+        function_name: &str,
+        args: Vec<Arg>,
+    ) {
+        self.try_new_component_with(name, blueprint_name, function_name, args)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-                lazy_static! {
-                    static ref COMPONENT_RE: Regex =
-                        Regex::new(r#"ComponentAddress\("(\w*)"\)"#).unwrap();
-                }
+    /// Creates a new Component of a given blueprint with a given function, or returns an error if the name is already
+    /// used, the blueprint or a resource of the arguments does not exist or the instantiation failed
+    ///
+    /// # Arguments
+    /// * `name` - name associated to the component
+    /// * `blueprint_name` - name of the blueprint
+    /// * `function_name` - name of the function instantiating the Component
+    /// * `args` - value of the arguments of the function
+    pub fn try_new_component_with(
+        &mut self,
+        name: &str,
+        blueprint_name: &str,
+        function_name: &str,
+        args: Vec<Arg>,
+    ) -> Result<(), SqrtError> {
+        if self.components.contains_key(name) {
+            return Err(SqrtError::NameConflict(name.to_string()));
+        }
 
-                let component_address = &COMPONENT_RE.captures(&output.0).expect(&format!(
-                    "Something went wrong when trying to instantiate blueprint! \n{}",
-                    output.0
-                ))[1];
-
-                let opt_badge: Option<String> = match blueprint.has_admin_badge() {
-                    AdminBadge::Internal => {
-                        lazy_static! {
-                            static ref ADMIN_BADGE: Regex =
-                                Regex::new(r#"Resource: (\w*)"#).unwrap();
-                        }
-
-                        let badge = &ADMIN_BADGE
-                            .captures(&output.0)
-                            .expect("Could not read admin badge address!")[1];
-                        Some(String::from(badge))
-                    }
+        let package = self.current_package_rc()?;
+        let blueprint = match package.get_blueprint(blueprint_name) {
+            Some(box_blueprint) => box_blueprint.as_ref(),
+            None => return Err(SqrtError::UnknownBlueprint(blueprint_name.to_string())),
+        };
 
-                    AdminBadge::External(admin_badge_address) => Some(
-                        self.resource_manager
-                            .get_address(&admin_badge_address)
-                            .clone(),
-                    ),
+        let (manifest_name, output_index, env_binding) =
+            self.function_bindings(blueprint, function_name, package.address(), &args)?;
+        let balances_before = self.current_balances();
+        let path = self.manifest_path(&manifest_name, false);
+        let (_, stdout, stderr) = self.run_manifest(&path, env_binding)?;
 
-                    AdminBadge::None => None,
-                };
+        let receipt = Receipt::from_stdout(&stdout);
+        if receipt.status == TransactionStatus::Unknown {
+            return Err(SqrtError::ResimFailure { stdout, stderr });
+        }
+        if !receipt.is_success() {
+            self.try_update()?;
+            return Err(SqrtError::TransactionFailed(Box::new(receipt)));
+        }
 
-                let comp = Component::from(component_address, package.path(), opt_badge);
-                self.components.insert(String::from(name), comp);
+        let returned_value = match ReturnValue::from_receipt(&stdout).get(output_index) {
+            None => {
+                return Err(SqrtError::UnexpectedOutput(format!(
+                    "Something went wrong when trying to instantiate blueprint! \n{}",
+                    stdout
+                )))
+            }
+            Some(value) => value.clone(),
+        };
+        let received = self.received_resources(&balances_before, &args, &receipt)?;
+        let bucket_resources = Self::bucket_resources(&returned_value, &received, &receipt);
+
+        self.register_instantiation(
+            name,
+            package.path(),
+            blueprint,
+            function_name,
+            returned_value,
+            bucket_resources,
+        )
+    }
 
-                if self.current_component.is_none() {
-                    self.set_current_component(name);
-                }
+    /// Creates a [`ManifestCall`] for a given function of a blueprint of the current package
+    ///
+    /// # Arguments
+    /// * `blueprint_name` - name of the blueprint
+    /// * `function_name` - name of the function to call
+    /// * `args` - value of the arguments of the function
+    pub fn call_function(
+        &mut self,
+        blueprint_name: &str,
+        function_name: &str,
+        args: Vec<Arg>,
+    ) -> ManifestCall<'_> {
+        self.try_call_function(blueprint_name, function_name, args)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
 
-                self.resource_manager.update_resources();
-                self.update_current_account();
-            }
-            None => {
-                panic!(
-                    "Could not find a blueprint named {} for current the package",
-                    blueprint_name
-                );
+    /// Creates a [`ManifestCall`] for a given function of a blueprint of the current package, or returns an error if
+    /// the blueprint or an entity of the arguments does not exist
+    ///
+    /// # Arguments
+    /// * `blueprint_name` - name of the blueprint
+    /// * `function_name` - name of the function to call
+    /// * `args` - value of the arguments of the function
+    pub fn try_call_function(
+        &mut self,
+        blueprint_name: &str,
+        function_name: &str,
+        args: Vec<Arg>,
+    ) -> Result<ManifestCall<'_>, SqrtError> {
+        let package = self.current_package_rc()?;
+        match package.get_blueprint(blueprint_name) {
+            Some(blueprint) => {
+                let (manifest_name, _, mut env_binding) = self.function_bindings(
+                    blueprint.as_ref(),
+                    function_name,
+                    package.address(),
+                    &args,
+                )?;
+
+                Ok(ManifestCall::new(self)
+                    .call_manifest(&manifest_name, false)
+                    .add_bindings(&mut env_binding))
             }
+            None => Err(SqrtError::UnknownBlueprint(blueprint_name.to_string())),
         }
     }
 
+    /// Returns the ABI of a blueprint of a published package
+    ///
+    /// # Arguments
+    /// * `package_name` - name associated to the package
+    /// * `blueprint_name` - name of the blueprint in the package
+    pub fn export_abi(&mut self, package_name: &str, blueprint_name: &str) -> BlueprintAbi {
+        let package_address = match self.packages.get(&package_name.to_lowercase()) {
+            None => panic!("There is no package with name {}", package_name),
+            Some(package) => package.address().to_string(),
+        };
+
+        let json = self
+            .backend
+            .export_abi(&package_address, blueprint_name)
+            .unwrap_or_else(|error| panic!("{}", error));
+        BlueprintAbi::from_json(blueprint_name, &json)
+    }
+
     /// Manually adds an instantiated component to the [`TestEnvironment`]
     ///
     /// * `package` - package associated to the component
@@ -258,7 +645,12 @@ impl TestEnvironment {
         }
 
         let package = self.packages.get(package).unwrap();
-        let comp = Component::from(&component_address, package.path(), admin_badge_address);
+        let comp = Component::from(
+            &component_address,
+            package.path(),
+            admin_badge_address,
+            None,
+        );
         self.components.insert(component_name.to_string(), comp);
     }
 
@@ -270,10 +662,97 @@ impl TestEnvironment {
     where
         M: Method,
     {
-        let component_address = self.get_current_component().address().to_string();
-        let package_path = self.get_current_package().path().to_string();
-        let component_badge = self.get_current_component().admin_badge().clone();
-        self.call(method, component_address, package_path, component_badge)
+        self.try_call_method(method)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Creates a [`ManifestCall`] for the given method, or returns an error if there is no current component, the
+    /// arguments do not match the ABI of its blueprint, an entity of the arguments does not exist or the ABI could not
+    /// be read
+    ///
+    /// # Arguments
+    /// * `method` -  [Method] to call
+    pub fn try_call_method<M>(&mut self, method: M) -> Result<ManifestCall<'_>, SqrtError>
+    where
+        M: Method,
+    {
+        let component_name = self
+            .current_component
+            .clone()
+            .ok_or(SqrtError::NoCurrentComponent)?;
+        let component = self.component(&component_name);
+        let component_address = component.address().to_string();
+        let component_badge = component.admin_badge().clone();
+        if method.custom_manifest_name().is_none() {
+            self.check_method_args(&component_name, &method)?;
+        }
+        self.call(method, component_address, component_badge)
+    }
+
+    /// Creates a [`TransactionBuilder`] to call several methods in the same transaction
+    pub fn transaction(&mut self) -> TransactionBuilder<'_> {
+        TransactionBuilder::new(self)
+    }
+
+    /// Sets how the transactions generated from now on lock their fee
+    ///
+    /// By default, 100 XRD are locked from the caller. Custom manifests lock their fee themselves.
+    /// # Arguments
+    /// * `fee_lock` - how the fee is locked
+    pub fn set_fee_lock(&mut self, fee_lock: FeeLock) {
+        self.try_set_fee_lock(fee_lock)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Sets how the transactions generated from now on lock their fee, or returns an error if the payer does not exist
+    ///
+    /// # Arguments
+    /// * `fee_lock` - how the fee is locked
+    pub fn try_set_fee_lock(&mut self, fee_lock: FeeLock) -> Result<(), SqrtError> {
+        match fee_lock.payer() {
+            FeePayer::Caller => {}
+            FeePayer::Component(name) => {
+                self.component_address(name)?;
+            }
+            FeePayer::Account(name) => {
+                self.account_address(&name.to_lowercase())?;
+            }
+        }
+        self.fee_lock = fee_lock;
+        Ok(())
+    }
+
+    /// Returns how the generated transactions lock their fee
+    pub fn fee_lock(&self) -> &FeeLock {
+        &self.fee_lock
+    }
+
+    /// Sets how the costs of the calls tracked with [`ManifestCall::track_cost`] are checked against their baseline
+    ///
+    /// By default, a warning is printed when a call costs more than 10% above its baseline.
+    /// # Arguments
+    /// * `cost_tracking` - maximal increase of the costs and what to do above it
+    pub fn set_cost_tracking(&mut self, cost_tracking: CostTracking) {
+        self.cost_tracking = cost_tracking;
+    }
+
+    /// Returns how the costs of tracked calls are checked
+    pub fn cost_tracking(&self) -> &CostTracking {
+        &self.cost_tracking
+    }
+
+    /// Returns the bindings of the variables of the fee locked by the generated transactions
+    pub(crate) fn fee_bindings(&self) -> Vec<(String, String)> {
+        vec![(Manifest::fee_payer_arg(), self.fee_payer_address())]
+    }
+
+    /// Returns the address of the entity locking the fee of the generated transactions
+    fn fee_payer_address(&self) -> String {
+        match self.fee_lock.payer() {
+            FeePayer::Caller => self.get_current_account().address().to_string(),
+            FeePayer::Component(name) => self.component(name).address().to_string(),
+            FeePayer::Account(name) => self.account(name).address().to_string(),
+        }
     }
 
     /// Creates a custom [`ManifestCall`] for the given Manifest
@@ -286,23 +765,71 @@ impl TestEnvironment {
         name: &str,
         env_args: Vec<(String, Arg)>,
     ) -> ManifestCall {
-        let (args_name, args): (Vec<String>, Vec<Arg>) = env_args.into_iter().unzip();
-        let mut tmp_bindings = vec![];
-        self.generate_bindings(&args, &mut tmp_bindings);
-
-        let (_, args_value): (Vec<String>, Vec<String>) = tmp_bindings.into_iter().unzip();
-        let mut final_bindings: Vec<(String, String)> =
-            args_name.into_iter().zip(args_value).collect();
+        let mut final_bindings: Vec<(String, String)> = env_args
+            .into_iter()
+            .map(|(name, arg)| {
+                let value = arg
+                    .to_manifest_value(self)
+                    .unwrap_or_else(|error| panic!("{}", error));
+                (name, value.binding())
+            })
+            .collect();
 
         ManifestCall::new(self)
             .call_manifest(name, true)
             .add_bindings(&mut final_bindings)
     }
 
+    /// Saves the current state of the ledger and of the environment
+    ///
+    /// The returned [`Snapshot`] can be given to [`TestEnvironment::restore`] any number of times, on this environment
+    /// or on another one using the same kind of backend, to run several tests from the same fixture without rebuilding
+    /// it.
+    pub fn snapshot(&mut self) -> Snapshot {
+        Snapshot {
+            ledger: self.backend.snapshot(),
+            accounts: self.accounts.clone(),
+            packages: self.packages.clone(),
+            components: self.components.clone(),
+            resource_manager: self.resource_manager.clone(),
+            current_account: self.current_account.clone(),
+            current_package: self.current_package.clone(),
+            current_component: self.current_component.clone(),
+            abis: self.abis.clone(),
+            fee_lock: self.fee_lock.clone(),
+            cost_tracking: self.cost_tracking.clone(),
+        }
+    }
+
+    /// Restores the ledger and the environment to a saved state
+    ///
+    /// # Arguments
+    /// * `snapshot` - state returned by [`TestEnvironment::snapshot`]
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.backend.restore(snapshot.ledger.as_ref());
+        self.accounts = snapshot.accounts.clone();
+        self.packages = snapshot.packages.clone();
+        self.components = snapshot.components.clone();
+        self.resource_manager = snapshot.resource_manager.clone();
+        self.current_account = snapshot.current_account.clone();
+        self.current_package = snapshot.current_package.clone();
+        self.current_component = snapshot.current_component.clone();
+        self.abis = snapshot.abis.clone();
+        self.fee_lock = snapshot.fee_lock.clone();
+        self.cost_tracking = snapshot.cost_tracking.clone();
+    }
+
     /// Updates the resources and the current account
     pub fn update(&mut self) {
-        self.resource_manager.update_resources();
-        self.update_current_account();
+        self.try_update()
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Updates the resources and the current account, or returns an error if they could not be read from the ledger
+    pub fn try_update(&mut self) -> Result<(), SqrtError> {
+        self.resource_manager
+            .update_resources(self.backend.as_mut())?;
+        self.update_current_account()
     }
 
     /// Transfers a given amount of tokens from the current account to a given account
@@ -312,29 +839,35 @@ impl TestEnvironment {
     /// * `token` -  name associated to the token to transfer
     /// * `amount` - amount of the token to transfer
     pub fn transfer_to(&mut self, account_name: &str, token: &str, amount: Decimal) {
-        match self.accounts.get(account_name) {
-            None => {
-                panic!("Account {} does not exist", account_name)
-            }
-            Some(acc) => {
-                let account_address = acc.address().to_string();
-                let owned = self.amount_owned_by_current(token);
-                if owned < amount {
-                    panic!(
-                        "Current account does not own enough token {} (owns {})",
-                        token, owned
-                    )
-                } else {
-                    let transfer = Deposit {
-                        amount,
-                        resource: token.to_string(),
-                    };
-                    let package_path = self.get_current_package().path().to_string();
-                    self.call(transfer, account_address, package_path, None)
-                        .run();
-                }
-            }
+        self.try_transfer_to(account_name, token, amount)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Transfers a given amount of tokens from the current account to a given account, or returns an error if the
+    /// account or the token does not exist, the current account does not own enough tokens or the transfer failed
+    ///
+    /// # Arguments
+    /// * `account_name` - name associated to the receiver
+    /// * `token` -  name associated to the token to transfer
+    /// * `amount` - amount of the token to transfer
+    pub fn try_transfer_to(
+        &mut self,
+        account_name: &str,
+        token: &str,
+        amount: Decimal,
+    ) -> Result<(), SqrtError> {
+        let account_address = self.account_address(account_name)?;
+        let owned = self.try_amount_owned_by_current(token)?;
+        if owned < amount {
+            return Err(SqrtError::NotEnoughTokens(token.to_string(), owned));
         }
+
+        let transfer = Deposit {
+            amount,
+            resource: token.to_string(),
+        };
+        self.call(transfer, account_address, None)?.try_run()?;
+        Ok(())
     }
 
     /// Sets the epoch to the given number
@@ -342,24 +875,16 @@ impl TestEnvironment {
     /// # Arguments
     /// * `epoch` - new epoch
     pub fn set_current_epoch(&mut self, epoch: u64) {
-        run_command(
-            Command::new("resim")
-                .arg("set-current-epoch")
-                .arg(epoch.to_string()),
-            false,
-        );
+        self.backend
+            .set_current_epoch(epoch)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
     /// Sets the current time
     pub fn set_current_time(&mut self, time: Instant) {
-        let utc_time = UtcDateTime::from_instant(&time).unwrap();
-
-        run_command(
-            Command::new("resim")
-                .arg("set-current-time")
-                .arg(format!("{}", utc_time)),
-            false,
-        );
+        self.backend
+            .set_current_time(time)
+            .unwrap_or_else(|error| panic!("{}", error))
     }
 
     /// Sets the current account to be used
@@ -367,21 +892,28 @@ impl TestEnvironment {
     /// # Arguments
     /// * `account_name` -  name associated to the account to use as current account
     pub fn set_current_account(&mut self, account_name: &str) {
+        self.try_set_current_account(account_name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Sets the current account to be used, or returns an error if it does not exist
+    ///
+    /// # Arguments
+    /// * `account_name` -  name associated to the account to use as current account
+    pub fn try_set_current_account(&mut self, account_name: &str) -> Result<(), SqrtError> {
         let real_name = String::from(account_name).to_lowercase();
         let account = self
             .accounts
             .get(&real_name)
-            .expect("Given account does not exist");
-        run_command(
-            Command::new("resim")
-                .arg("set-default-account")
-                .arg(account.address())
-                .arg(account.private_key())
-                .arg(account.owner_badge()),
-            false,
-        );
+            .ok_or_else(|| SqrtError::UnknownAccount(account_name.to_string()))?;
+        self.backend.set_default_account(
+            account.address(),
+            account.private_key(),
+            account.owner_badge(),
+        )?;
 
         self.current_account = real_name;
+        Ok(())
     }
 
     /// Returns the address of the current acocunt
@@ -399,7 +931,19 @@ impl TestEnvironment {
     /// # Arguments
     /// * `name` - name given to the account for which to get the address
     pub fn get_account_address(&self, name: &str) -> &str {
-        self.get_account(name).unwrap().address()
+        self.try_get_account_address(name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns the address of a given account, or an error if it does not exist
+    ///
+    /// # Arguments
+    /// * `name` - name given to the account for which to get the address
+    pub fn try_get_account_address(&self, name: &str) -> Result<&str, SqrtError> {
+        match self.get_account(name) {
+            None => Err(SqrtError::UnknownAccount(name.to_string())),
+            Some(account) => Ok(account.address()),
+        }
     }
 
     /// Returns the address of a given Resource
@@ -410,17 +954,61 @@ impl TestEnvironment {
         self.resource_manager.get_address(name)
     }
 
+    /// Returns the address of a given Resource, or an error if it does not exist
+    ///
+    /// # Arguments
+    /// * `name` -  name associated to the resource
+    pub fn try_get_resource(&self, name: &str) -> Result<&String, SqrtError> {
+        self.resource_manager
+            .try_get_address(name)
+            .ok_or_else(|| SqrtError::UnknownResource(name.to_string()))
+    }
+
+    /// Returns the metadata of a given Resource, read from the ledger
+    ///
+    /// # Arguments
+    /// * `resource_name` - name associated to the resource
+    pub fn get_metadata(&mut self, resource_name: &str) -> HashMap<String, String> {
+        self.try_get_metadata(resource_name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns the metadata of a given Resource, or an error if it does not exist or cannot be read
+    ///
+    /// # Arguments
+    /// * `resource_name` - name associated to the resource
+    pub fn try_get_metadata(
+        &mut self,
+        resource_name: &str,
+    ) -> Result<HashMap<String, String>, SqrtError> {
+        let address = self.try_get_resource(resource_name)?.clone();
+        self.backend.metadata(&address)
+    }
+
     /// Returns the amount of a given Resource owned by a given account
     ///
     /// # Arguments
     /// * `account_name` -  name associated to the account
     /// * `resource_name` - name associated to the resource
     pub fn amount_owned_by(&self, account_name: &str, resource_name: &str) -> Decimal {
+        self.try_amount_owned_by(account_name, resource_name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns the amount of a given Resource owned by a given account, or an error if the account or the resource
+    /// does not exist
+    ///
+    /// # Arguments
+    /// * `account_name` -  name associated to the account
+    /// * `resource_name` - name associated to the resource
+    pub fn try_amount_owned_by(
+        &self,
+        account_name: &str,
+        resource_name: &str,
+    ) -> Result<Decimal, SqrtError> {
         match self.accounts.get(account_name) {
-            None => {
-                panic!("The account {} does not exist", account_name)
-            }
-            Some(acc) => acc.amount_owned(self.get_resource(resource_name)),
+            None => Err(SqrtError::UnknownAccount(account_name.to_string())),
+            Some(acc) => Ok(acc.amount_owned(self.try_get_resource(resource_name)?)),
         }
     }
 
@@ -429,8 +1017,18 @@ impl TestEnvironment {
     /// # Arguments
     /// * `resource_name` - name associated to the resource
     pub fn amount_owned_by_current(&self, resource_name: &str) -> Decimal {
-        self.get_current_account()
-            .amount_owned(self.get_resource(resource_name))
+        self.try_amount_owned_by_current(resource_name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns the amount of a given Resource owned by the current account, or an error if the resource does not exist
+    ///
+    /// # Arguments
+    /// * `resource_name` - name associated to the resource
+    pub fn try_amount_owned_by_current(&self, resource_name: &str) -> Result<Decimal, SqrtError> {
+        Ok(self
+            .get_current_account()
+            .amount_owned(self.try_get_resource(resource_name)?))
     }
 
     /// Returns the ids owned by a given account for a given Non Fungible Resource
@@ -443,13 +1041,24 @@ impl TestEnvironment {
         account_name: &str,
         resource_name: &str,
     ) -> Option<&Vec<String>> {
+        self.try_get_non_fungible_ids_owned_by(account_name, resource_name)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    /// Returns the ids owned by a given account for a given Non Fungible Resource, or an error if the account or the
+    /// resource does not exist
+    ///
+    /// # Arguments
+    /// * `account_name` -  name associated to the account
+    /// * `resource_name` - name associated to the resource
+    pub fn try_get_non_fungible_ids_owned_by(
+        &self,
+        account_name: &str,
+        resource_name: &str,
+    ) -> Result<Option<&Vec<String>>, SqrtError> {
         match self.accounts.get(account_name) {
-            None => {
-                panic!("The account {} does not exist", account_name)
-            }
-            Some(acc) => {
-                acc.get_non_fungibles_ids(self.resource_manager.get_address(resource_name))
-            }
+            None => Err(SqrtError::UnknownAccount(account_name.to_string())),
+            Some(acc) => Ok(acc.get_non_fungibles_ids(self.try_get_resource(resource_name)?)),
         }
     }
 
@@ -535,9 +1144,144 @@ impl TestEnvironment {
         }
     }
 
-    fn update_current_account(&mut self) {
+    pub(crate) fn component(&self, component_name: &str) -> &Component {
+        match self.components.get(component_name) {
+            None => panic!("There is no component with name {}", component_name),
+            Some(component) => component,
+        }
+    }
+
+    fn current_package_rc(&self) -> Result<Arc<Package>, SqrtError> {
+        match &self.current_package {
+            None => Err(SqrtError::NoCurrentPackage),
+            Some(current) => Ok(Arc::clone(self.packages.get(current).unwrap())),
+        }
+    }
+
+    /// Returns the state of a given component
+    ///
+    /// # Arguments
+    /// * `component_name` -  name associated to the component
+    pub fn component_state(&mut self, component_name: &str) -> ComponentState {
+        let address = match self.components.get(component_name) {
+            None => panic!("There is no component with name {}", component_name),
+            Some(comp) => comp.address().to_string(),
+        };
+
+        let fields = self
+            .backend
+            .component_fields(&address)
+            .unwrap_or_else(|error| panic!("{}", error));
+        let balances = self
+            .backend
+            .balances(&address)
+            .unwrap_or_else(|error| panic!("{}", error));
+        ComponentState::new(fields, balances)
+    }
+
+    /// Returns the amount of a given resource owned by a given account, read directly from the ledger
+    pub(crate) fn balance_of(&mut self, account_name: &str, resource_name: &str) -> Decimal {
+        let account_address = match self.accounts.get(&account_name.to_lowercase()) {
+            None => panic!("The account {} does not exist", account_name),
+            Some(acc) => acc.address().to_string(),
+        };
+
+        // Resources created by the transaction are not known before it runs
+        if !self.resource_manager.exists(&resource_name.to_lowercase()) {
+            return Decimal::zero();
+        }
+
+        let resource_address = self.resource_manager.get_address(resource_name).clone();
+        self.backend
+            .balances(&account_address)
+            .unwrap_or_else(|error| panic!("{}", error))
+            .iter()
+            .filter(|balance| balance.address == resource_address)
+            .fold(Decimal::zero(), |total, balance| {
+                if balance.non_fungible_ids.is_empty() {
+                    total + balance.amount
+                } else {
+                    total + Decimal::from(balance.non_fungible_ids.len())
+                }
+            })
+    }
+
+    fn update_current_account(&mut self) -> Result<(), SqrtError> {
         let account = self.accounts.get_mut(&self.current_account).unwrap();
-        self.resource_manager.update_resources_for_account(account);
+        self.resource_manager
+            .update_resources_for_account(account, self.backend.as_mut())
+    }
+
+    /// Resets a backend and returns its default account with the resources of the ledger
+    fn initialize(backend: &mut dyn Backend) -> Result<(Account, ResourceManager), SqrtError> {
+        backend.reset()?;
+
+        let mut default_account = Account::new(backend)?;
+        let mut resource_manager = ResourceManager::new(backend)?;
+        resource_manager.submit_owner_badge(&mut default_account, "default", backend)?;
+        Ok((default_account, resource_manager))
+    }
+
+    /// Returns the amount of every resource owned by the current account, by address
+    pub(crate) fn current_balances(&self) -> HashMap<String, Decimal> {
+        self.get_current_account().balances()
+    }
+
+    pub(crate) fn backend_mut(&mut self) -> &mut dyn Backend {
+        self.backend.as_mut()
+    }
+
+    /// Returns the directory in which the manifests generated by the environment are written
+    ///
+    /// The directory is deleted when the environment is dropped.
+    pub fn manifests_dir(&self) -> &Path {
+        self.manifests_dir.path()
+    }
+
+    /// Returns the path of a manifest, custom manifests being read from the current package
+    ///
+    /// # Arguments
+    /// * `name` - name of the manifest
+    /// * `custom_manifest` - whether the manifest is a custom one
+    pub(crate) fn manifest_path(&self, name: &str, custom_manifest: bool) -> String {
+        if custom_manifest {
+            custom_manifest_path(self.get_current_package().path(), name)
+        } else {
+            generated_manifest_path(self.manifests_dir(), name)
+        }
+    }
+
+    /// Writes a generated manifest and returns its path
+    ///
+    /// # Arguments
+    /// * `output` - content of the manifest
+    /// * `name` - name of the manifest
+    pub(crate) fn write_manifest(&self, output: String, name: &str) -> String {
+        write_manifest(output, self.manifests_dir(), name)
+    }
+
+    /// Runs a manifest signed by the accounts whose authorization its transaction needs
+    ///
+    /// # Arguments
+    /// * `path` - path of the manifest
+    /// * `env_bindings` - values of the environment variables of the manifest
+    pub(crate) fn run_manifest(
+        &mut self,
+        path: &str,
+        env_bindings: Vec<(String, String)>,
+    ) -> Result<(String, String, String), SqrtError> {
+        let signers = self.signers();
+        run_manifest(self.backend.as_mut(), path, env_bindings, &signers)
+    }
+
+    /// Returns the accounts signing the transactions, besides the current account when another account pays the fee
+    fn signers(&self) -> Vec<AccountInfo> {
+        match self.fee_lock.payer() {
+            FeePayer::Account(name) if name.to_lowercase() != self.current_account => {
+                vec![self.get_current_account().info(), self.account(name).info()]
+            }
+            _ => vec![],
+        }
     }
 
     fn get_current_account(&self) -> &Account {
@@ -548,202 +1292,399 @@ impl TestEnvironment {
         self.accounts.get(name)
     }
 
-    fn create_instantiation_manifest<B>(path: &str, blueprint: &B, args: &Vec<Arg>) -> String
+    fn account(&self, name: &str) -> &Account {
+        match self.get_account(&name.to_lowercase()) {
+            None => panic!("There is no account with name {}", name),
+            Some(account) => account,
+        }
+    }
+
+    fn create_function_manifest<B>(
+        &self,
+        blueprint: &B,
+        function_name: &str,
+        args: &[Arg],
+    ) -> Result<usize, SqrtError>
     where
         B: Blueprint + ?Sized,
     {
-        let mut manifest = Manifest::new();
-        manifest.instantiate(blueprint, args);
+        let mut manifest = Manifest::with_fee_lock(&self.fee_lock);
+        manifest.call_function(blueprint.name(), function_name, args, self)?;
         let manifest_string = manifest.build();
-        let name = format!("{}_instantiation", blueprint.name());
-        write_manifest(manifest_string, path, name.as_str())
+        let name = Self::function_manifest_name(blueprint, function_name);
+        self.write_manifest(manifest_string, name.as_str());
+
+        // Every instruction of the manifest has an output, the one of CALL_FUNCTION being the returned value
+        Ok(manifest
+            .instructions()
+            .iter()
+            .position(|instruction| matches!(instruction, Instruction::CallFunction { .. }))
+            .unwrap())
     }
 
-    fn create_method_manifest<M>(path: &str, method: &M)
+    fn function_manifest_name<B>(blueprint: &B, function_name: &str) -> String
+    where
+        B: Blueprint + ?Sized,
+    {
+        if function_name == blueprint.instantiation_name() {
+            format!("{}_instantiation", blueprint.name())
+        } else {
+            format!("{}_{}", blueprint.name(), function_name)
+        }
+    }
+
+    fn create_method_manifest<M>(&self, method: &M) -> Result<(), SqrtError>
     where
         M: Method,
     {
-        let mut manifest = Manifest::new();
-        manifest.call_method(method);
+        let mut manifest = Manifest::with_fee_lock(&self.fee_lock);
+        manifest.call_method(method, self)?;
         let manifest_string = manifest.build();
         let manifest_name = match method.custom_manifest_name() {
             None => method.name(),
             Some(name) => name,
         };
-        write_manifest(manifest_string, path, manifest_name);
+        self.write_manifest(manifest_string, manifest_name);
+        Ok(())
     }
 
-    fn get_binding_for(&self, arg: &Arg, arg_count: u32) -> (String, String) {
-        let arg_name = format!("arg_{}", arg_count);
-        let arg_value = match arg {
-            Arg::Unit => {
-                panic!("Should not happen")
-            }
-            Arg::Bool(value) => {
-                format!("{}", *value)
-            }
-            Arg::I8(value) => {
-                format!("{}", *value)
-            }
-            Arg::I16(value) => {
-                format!("{}", *value)
-            }
-            Arg::I32(value) => {
-                format!("{}", *value)
-            }
-            Arg::I64(value) => {
-                format!("{}", *value)
-            }
-            Arg::I128(value) => {
-                format!("{}", *value)
-            }
-            Arg::U8(value) => {
-                format!("{}", *value)
-            }
-            Arg::U16(value) => {
-                format!("{}", *value)
-            }
-            Arg::U32(value) => {
-                format!("{}", *value)
-            }
-            Arg::U64(value) => {
-                format!("{}", *value)
-            }
-            Arg::U128(value) => {
-                format!("{}", *value)
-            }
-            Arg::StringArg(value)
-            | Arg::SystemAddressArg(value)
-            | Arg::Expression(value)
-            | Arg::Blob(value)
-            | Arg::HashArg(value)
-            | Arg::EcdsaSecp256k1PublicKeyArg(value)
-            | Arg::EcdsaSecp256k1Signature(value)
-            | Arg::EddsaEd25519PublicKey(value)
-            | Arg::EddsaEd25519Signature(value) => {
-                format!("{}", *value)
-            }
+    fn register_instantiation<B>(
+        &mut self,
+        name: &str,
+        package_path: &str,
+        blueprint: &B,
+        function_name: &str,
+        returned_value: ReturnValue,
+        bucket_resources: Option<Vec<String>>,
+    ) -> Result<(), SqrtError>
+    where
+        B: Blueprint + ?Sized,
+    {
+        let returned_values = match returned_value {
+            ReturnValue::Tuple(elements) => elements,
+            value => vec![value],
+        };
+        let returned_items = blueprint.function_return(function_name);
+        if returned_items.len() != returned_values.len() {
+            return Err(SqrtError::UnexpectedOutput(format!(
+                "Function {} of {} returned {} elements but {} were expected",
+                function_name,
+                blueprint.name(),
+                returned_values.len(),
+                returned_items.len()
+            )));
+        }
 
-            Arg::EnumArg(variant, fields) => {
-                format!("{}u8, {}", variant, self.get_binding_for_elements(fields))
+        let registers_bucket = returned_items
+            .iter()
+            .any(|item| matches!(item, ReturnedItem::AdminBadge | ReturnedItem::Resource(_)));
+        let mut received = match bucket_resources {
+            Some(resources) => resources.into_iter(),
+            None if registers_bucket => {
+                return Err(SqrtError::UnexpectedOutput(format!(
+                    "Could not tell which resources are held by the buckets returned by function {} of {}",
+                    function_name,
+                    blueprint.name()
+                )))
             }
-            Arg::TupleArg(elements) | Arg::VecArg(elements) => {
-                format!("{}", self.get_binding_for_elements(elements))
-            }
-            Arg::HashMapArg(elements) => {
-                let mut string = String::new();
-                for (key_arg, value_arg) in elements {
-                    let (_, key_value) = self.get_binding_for(key_arg, 0);
-                    let (_, value_value) = self.get_binding_for(value_arg, 0);
-                    string = format!("{}Tuple({}, {}), ", string, key_value, value_value);
+            None => vec![].into_iter(),
+        };
+
+        let mut component_address = None;
+        let mut other_components = vec![];
+        let mut returned_badge = None;
+        let mut resources = vec![];
+        for (position, (item, value)) in returned_items.iter().zip(returned_values).enumerate() {
+            match (item, value) {
+                (ReturnedItem::Component, ReturnValue::ComponentAddress(address)) => {
+                    component_address = Some(address);
                 }
-                string.pop();
-                string.pop();
-                string
-            }
-            Arg::PackageAddressArg(name) => match self.packages.get(name) {
-                None => {
-                    panic!("No package with name {}", name)
+                (ReturnedItem::OtherComponent(suffix), ReturnValue::ComponentAddress(address)) => {
+                    let component_name = format!("{}_{}", name, suffix);
+                    if self.components.contains_key(&component_name) {
+                        return Err(SqrtError::NameConflict(component_name));
+                    }
+                    other_components.push((component_name, address));
                 }
-                Some(package) => String::from(package.address()),
-            },
-            Arg::ComponentAddressArg(name) => match self.get_component(&name) {
-                None => {
-                    panic!("No components with name {}", name)
+                (ReturnedItem::AdminBadge, ReturnValue::Bucket(_)) => {
+                    returned_badge = received.next();
                 }
-                Some(comp) => String::from(comp),
-            },
-            Arg::AccountAddressArg(name) => match self.get_account(&name) {
-                None => {
-                    panic!("No account with name {}", name)
+                (ReturnedItem::Resource(resource_name), ReturnValue::Bucket(_)) => {
+                    let address = received.next().ok_or_else(|| {
+                        SqrtError::UnexpectedOutput(format!(
+                            "Could not read the address of resource {}",
+                            resource_name
+                        ))
+                    })?;
+                    resources.push((resource_name.clone(), address));
+                }
+                (ReturnedItem::Ignored, ReturnValue::Bucket(_)) => {
+                    received.next();
+                }
+                (ReturnedItem::Ignored, _) => {}
+                (_, value) => {
+                    return Err(SqrtError::UnexpectedOutput(format!(
+                        "Element {} returned by function {} of {} does not match the expected shape: {:?}",
+                        position,
+                        function_name,
+                        blueprint.name(),
+                        value
+                    )))
                 }
-                Some(account) => String::from(account.address()),
-            },
-
-            Arg::ResourceAddressArg(name) => self.resource_manager.get_address(name).clone(),
-            Arg::DecimalArg(value) => value.to_string(),
-            Arg::PreciseDecimalArg(value) => value.to_string(),
-            Arg::NonFungibleLocalId(arg) => {
-                let (_, value) = self.get_binding_for(arg.as_ref(), arg_count);
-                value
-            }
-            Arg::FungibleBucketArg(_, _)
-            | Arg::NonFungibleBucketArg(_, _)
-            | Arg::FungibleProofArg(_, _)
-            | Arg::NonFungibleProofArg(_, _) => {
-                panic!("This should not happen")
             }
-            Arg::NonFungibleGlobalAddress(name, arg) => {
-                let (_, id_value) = self.get_binding_for(arg.as_ref(), 0);
-                let resource_value = self.resource_manager.get_address(name);
+        }
 
-                format!("{}, {}", resource_value, id_value)
+        let admin_badge = match blueprint.has_admin_badge() {
+            AdminBadge::Internal => Some(returned_badge.ok_or_else(|| {
+                SqrtError::UnexpectedOutput(String::from("Could not read admin badge address!"))
+            })?),
+            AdminBadge::External(admin_badge_name) => {
+                Some(self.try_get_resource(&admin_badge_name)?.clone())
             }
+            AdminBadge::None => returned_badge,
         };
+        let component_address = component_address.ok_or_else(|| {
+            SqrtError::UnexpectedOutput(format!(
+                "Function {} of {} did not return the component",
+                function_name,
+                blueprint.name()
+            ))
+        })?;
+
+        if !resources.is_empty() {
+            let ledger_resources = self.backend.resources()?;
+            for (resource_name, address) in resources {
+                let is_fungible = ledger_resources
+                    .iter()
+                    .find(|resource| resource.address == address)
+                    .is_none_or(|resource| resource.is_fungible);
+                self.resource_manager
+                    .add_resource(&resource_name, address, is_fungible);
+            }
+        }
+
+        let comp = Component::from(
+            &component_address,
+            package_path,
+            admin_badge.clone(),
+            Some(blueprint.name().to_string()),
+        );
+        self.components.insert(String::from(name), comp);
 
-        (arg_name, arg_value)
+        for (component_name, address) in other_components {
+            let comp = Component::from(&address, package_path, admin_badge.clone(), None);
+            self.components.insert(component_name, comp);
+        }
+
+        if self.current_component.is_none() {
+            self.set_current_component(name);
+        }
+
+        self.try_update()
+    }
+
+    /// Returns the resources held by the buckets returned by a function, in the order in which they were returned, or
+    /// `None` if they cannot be told apart
+    ///
+    /// As resim only displays the identifiers of the returned buckets, they are matched with the resources received
+    /// by the caller. A single bucket holds the single received resource. Several buckets can only hold new resources
+    /// and are matched with them by their identifiers, which increase as buckets are created, so that each bucket
+    /// gets the resource whose initial supply it holds.
+    /// # Arguments
+    /// * `returned_value` - value returned by the function
+    /// * `received` - addresses of the resources received by the caller
+    /// * `receipt` - receipt of the transaction
+    fn bucket_resources(
+        returned_value: &ReturnValue,
+        received: &[String],
+        receipt: &Receipt,
+    ) -> Option<Vec<String>> {
+        let returned_values = match returned_value {
+            ReturnValue::Tuple(elements) => elements.as_slice(),
+            value => std::slice::from_ref(value),
+        };
+        let buckets: Vec<&String> = returned_values
+            .iter()
+            .filter_map(|value| match value {
+                ReturnValue::Bucket(id) => Some(id),
+                _ => None,
+            })
+            .collect();
+
+        // Two buckets of the same resource, or a resource received otherwise, cannot be told apart
+        if buckets.len() != received.len() {
+            return None;
+        }
+        if buckets.len() <= 1 {
+            return Some(received.to_vec());
+        }
+
+        let new_resources: Vec<&String> = receipt
+            .new_entities
+            .resources
+            .iter()
+            .filter(|resource| received.contains(resource))
+            .collect();
+        if new_resources.len() != received.len() {
+            return None;
+        }
+
+        let mut ids = vec![];
+        for bucket in &buckets {
+            let digits: String = bucket
+                .trim_start_matches(|c: char| !c.is_ascii_digit())
+                .chars()
+                .take_while(char::is_ascii_digit)
+                .collect();
+            ids.push(digits.parse::<u64>().ok()?);
+        }
+        let mut positions: Vec<usize> = (0..buckets.len()).collect();
+        positions.sort_by_key(|&position| ids[position]);
+
+        let mut resources = vec![String::new(); buckets.len()];
+        for (position, resource) in positions.into_iter().zip(new_resources) {
+            resources[position] = resource.clone();
+        }
+        Some(resources)
     }
 
-    fn get_binding_for_elements(&self, args: &Vec<Arg>) -> String {
-        let mut string = String::new();
+    /// Returns the addresses of the resources received by the current account during a transaction
+    ///
+    /// The amounts taken from the account by the arguments, and by the fee when the account paid it, are added back to
+    /// the balances, so that the resources given back to the caller are found too.
+    fn received_resources(
+        &mut self,
+        balances_before: &HashMap<String, Decimal>,
+        args: &[Arg],
+        receipt: &Receipt,
+    ) -> Result<Vec<String>, SqrtError> {
+        let mut spent: HashMap<String, Decimal> = HashMap::new();
         for arg in args {
-            let (_, value) = self.get_binding_for(arg, 0);
-            let fake_generic = arg.to_generic(0);
-            let arg_str = fake_generic.replace("${arg_0}", &value);
-            string = format!("{}{}, ", string, arg_str)
+            let (name, amount) = match arg {
+                Arg::FungibleBucketArg(name, amount) => (name, *amount),
+                Arg::NonFungibleBucketArg(name, ids) => (name, Decimal::from(ids.len())),
+                _ => continue,
+            };
+            *spent
+                .entry(self.try_get_resource(name)?.clone())
+                .or_insert_with(Decimal::zero) += amount;
         }
-        string.pop();
-        string.pop();
-        string
+        let caller = self.get_current_account().address().to_string();
+        // The fee paid by the caller is taken from its XRD, which could otherwise hide XRD received in a bucket
+        if self.fee_payer_address() == caller {
+            if let (Some(fees), Some(radix)) = (
+                &receipt.fees,
+                self.resource_manager.try_get_address("radix"),
+            ) {
+                *spent.entry(radix.clone()).or_insert_with(Decimal::zero) += fees.total;
+            }
+        }
+
+        let received: Vec<String> = self
+            .backend
+            .balances(&caller)?
+            .into_iter()
+            .filter(|balance| {
+                let amount = if balance.non_fungible_ids.is_empty() {
+                    balance.amount
+                } else {
+                    Decimal::from(balance.non_fungible_ids.len())
+                };
+                let before = balances_before
+                    .get(&balance.address)
+                    .copied()
+                    .unwrap_or_else(Decimal::zero);
+                let spent = spent
+                    .get(&balance.address)
+                    .copied()
+                    .unwrap_or_else(Decimal::zero);
+                amount + spent > before
+            })
+            .map(|balance| balance.address)
+            .collect();
+        Ok(received)
     }
 
-    fn instantiate<B>(
+    fn function_bindings<B>(
         &self,
         blueprint: &B,
-        package_path: &str,
+        function_name: &str,
         package_address: &str,
         args: &Vec<Arg>,
-    ) -> (String, String)
+    ) -> Result<FunctionBindings, SqrtError>
     where
         B: Blueprint + ?Sized,
     {
-        let name = format!("{}_instantiation", blueprint.name());
-        if !generated_manifest_exists(name.as_str(), package_path) {
-            Self::create_instantiation_manifest(package_path, blueprint, args);
-        }
+        let name = Self::function_manifest_name(blueprint, function_name);
+        let output_index = self.create_function_manifest(blueprint, function_name, args)?;
 
         let account_comp = String::from(self.get_current_account().address());
 
-        let mut env_binding = vec![];
+        let mut env_binding = self.fee_bindings();
         env_binding.push((Manifest::caller_arg(), account_comp));
         env_binding.push((Manifest::package_arg(), package_address.to_string()));
 
-        self.generate_bindings(args, &mut env_binding);
-        let (_, stdout, stderr) = run_manifest(package_path, name.as_str(), false, env_binding);
-        (stdout, stderr)
+        Ok((name, output_index, env_binding))
     }
 
-    fn reset() {
-        run_command(Command::new("resim").arg("reset"), false);
+    /// Checks the arguments of a method against the ABI of the blueprint of a component
+    pub(crate) fn check_method_args<M>(
+        &mut self,
+        component_name: &str,
+        method: &M,
+    ) -> Result<(), SqrtError>
+    where
+        M: Method,
+    {
+        let component = self
+            .components
+            .get(component_name)
+            .ok_or_else(|| SqrtError::UnknownComponent(component_name.to_string()))?;
+        let blueprint_name = match component.blueprint_name() {
+            // Components added manually have no known blueprint
+            None => return Ok(()),
+            Some(name) => name.to_string(),
+        };
+        let package_address = match self
+            .packages
+            .values()
+            .find(|package| package.path() == component.package_path())
+        {
+            None => return Ok(()),
+            Some(package) => package.address().to_string(),
+        };
+
+        let key = (package_address, blueprint_name);
+        if !self.abis.contains_key(&key) {
+            let json = self.backend.export_abi(&key.0, &key.1)?;
+            let abi = BlueprintAbi::from_json(&key.1, &json);
+            self.abis.insert(key.clone(), abi);
+        }
+
+        match self.abis[&key].function(method.name()) {
+            None => Err(SqrtError::ArgumentMismatch(format!(
+                "Blueprint {} has no method named {}",
+                key.1,
+                method.name()
+            ))),
+            Some(function) => function.try_check_args(&method.args().unwrap_or_default()),
+        }
     }
 
     fn call<M>(
         &mut self,
         method: M,
         component_address: String,
-        package_path: String,
         component_badge: Option<String>,
-    ) -> ManifestCall
+    ) -> Result<ManifestCall<'_>, SqrtError>
     where
         M: Method,
     {
-        if !generated_manifest_exists(method.name(), &package_path) {
-            Self::create_method_manifest(&package_path, &method);
-        }
+        self.create_method_manifest(&method)?;
 
         let account_comp = String::from(self.get_current_account().address());
 
-        let mut env_binding = vec![];
+        let mut env_binding = self.fee_bindings();
         env_binding.push((Manifest::caller_arg(), account_comp));
         env_binding.push((Manifest::component_arg(), component_address));
         match component_badge {
@@ -752,71 +1693,39 @@ impl TestEnvironment {
                 env_binding.push((Manifest::admin_badge_arg(), badge));
             }
         }
-        match method.args() {
-            None => {}
-            Some(args_vec) => {
-                self.generate_bindings(&args_vec, &mut env_binding);
-            }
-        }
 
         let manifest_name = match method.custom_manifest_name() {
             None => method.name(),
             Some(name) => name,
         };
 
-        ManifestCall::new(self)
+        Ok(ManifestCall::new(self)
             .call_manifest(manifest_name, false)
-            .add_bindings(&mut env_binding)
+            .add_bindings(&mut env_binding))
     }
+}
 
-    fn generate_bindings(&self, args: &Vec<Arg>, env_binding: &mut Vec<(String, String)>) {
-        let mut arg_count = 0u32;
-        for arg in args {
-            match arg {
-                Arg::Unit => {}
-                Arg::FungibleBucketArg(name, amount) => {
-                    let resource_arg_name = format!("arg_{}_resource", arg_count);
-                    let amount_arg_name = format!("arg_{}_amount", arg_count);
-                    env_binding.push((resource_arg_name, self.get_resource(&name).clone()));
-                    env_binding.push((amount_arg_name, amount.to_string()));
-                }
-                Arg::NonFungibleBucketArg(name, ids) => {
-                    let resource_arg_name = format!("arg_{}_resource", arg_count);
-                    env_binding.push((resource_arg_name, self.get_resource(&name).clone()));
-
-                    let ids_arg_name = format!("arg_{}_ids", arg_count);
-                    let mut ids_arg_value = String::new();
-                    for id_value in ids {
-                        ids_arg_value =
-                            format!("{}NonFungibleLocalId(\"{}\") ,", ids_arg_value, id_value);
-                    }
-                    ids_arg_value.pop();
-                    ids_arg_value.pop();
-                    env_binding.push((ids_arg_name, ids_arg_value));
-                }
-                Arg::FungibleProofArg(name, amount) => {
-                    let resource_arg_name = format!("arg_{}_resource", arg_count);
-                    let amount_arg_name = format!("arg_{}_amount", arg_count);
-                    env_binding.push((resource_arg_name, self.get_resource(&name).clone()));
-                    env_binding.push((amount_arg_name, format!("Decimal(\"{}\")", amount)));
-                }
-                Arg::NonFungibleProofArg(name, ids) => {
-                    let resource_arg_name = format!("arg_{}_resource", arg_count);
-                    env_binding.push((resource_arg_name, self.get_resource(&name).clone()));
-
-                    let ids_arg_name = format!("arg_{}_ids", arg_count);
-                    let mut ids_arg_value = String::new();
-                    for id_value in ids {
-                        ids_arg_value =
-                            format!("{}NonFungibleLocalId(\"{}\") ,", ids_arg_value, id_value);
-                    }
-                    ids_arg_value.pop();
-                    ids_arg_value.pop();
-                    env_binding.push((ids_arg_name, ids_arg_value));
-                }
-                _ => env_binding.push(self.get_binding_for(&arg, arg_count)),
-            }
-            arg_count += 1;
-        }
+impl AddressBook for TestEnvironment {
+    fn package_address(&self, name: &str) -> Result<String, SqrtError> {
+        self.packages
+            .get(name)
+            .map(|package| package.address().to_string())
+            .ok_or_else(|| SqrtError::UnknownPackage(name.to_string()))
+    }
+
+    fn component_address(&self, name: &str) -> Result<String, SqrtError> {
+        self.get_component(name)
+            .map(String::from)
+            .ok_or_else(|| SqrtError::UnknownComponent(name.to_string()))
+    }
+
+    fn account_address(&self, name: &str) -> Result<String, SqrtError> {
+        self.get_account(name)
+            .map(|account| account.address().to_string())
+            .ok_or_else(|| SqrtError::UnknownAccount(name.to_string()))
+    }
+
+    fn resource_address(&self, name: &str) -> Result<String, SqrtError> {
+        self.try_get_resource(name).cloned()
     }
 }
@@ -0,0 +1,93 @@
+//! Composes several method calls into a single transaction
+
+use crate::manifest::Manifest;
+use crate::manifest_call::ManifestCall;
+use crate::method::Method;
+use crate::test_environment::TestEnvironment;
+
+/// Builds a transaction calling several methods, possibly on different components, in one manifest
+///
+/// Buckets returned by a call stay on the worktop and can be used by later calls with
+/// [`Arg::WorktopBucketArg`](crate::method::Arg::WorktopBucketArg). Everything left on the worktop at the end of the
+/// transaction is deposited in the account of the caller.
+pub struct TransactionBuilder<'a> {
+    test_environment: &'a mut TestEnvironment,
+    manifest: Manifest,
+    env_bindings: Vec<(String, String)>,
+    method_names: Vec<String>,
+}
+
+impl<'a> TransactionBuilder<'a> {
+    /// Returns a new TransactionBuilder
+    ///
+    /// # Arguments
+    /// * `test_environment` - [`TestEnvironment`] of the transaction
+    pub fn new(test_environment: &'a mut TestEnvironment) -> TransactionBuilder<'a> {
+        let mut manifest = Manifest::with_fee_lock(test_environment.fee_lock());
+        manifest.lock_bound_fee();
+        let caller = test_environment.get_current_account_address().to_string();
+        let mut env_bindings = test_environment.fee_bindings();
+        env_bindings.push((Manifest::caller_arg(), caller));
+
+        TransactionBuilder {
+            test_environment,
+            manifest,
+            env_bindings,
+            method_names: vec![],
+        }
+    }
+
+    /// Adds a call to a method of a component to the transaction
+    ///
+    /// The custom manifest of the method, if any, is ignored.
+    /// # Arguments
+    /// * `component_name` - name of the component to call
+    /// * `method` - [Method] to call
+    pub fn call_method<M>(mut self, component_name: &str, method: M) -> TransactionBuilder<'a>
+    where
+        M: Method,
+    {
+        self.test_environment
+            .check_method_args(component_name, &method)
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        let index = self.method_names.len();
+        let component_arg = format!("{}_{}", Manifest::component_arg(), index);
+        let admin_badge_arg = format!("{}_{}", Manifest::admin_badge_arg(), index);
+        self.manifest
+            .add_method_call(
+                &method,
+                &component_arg,
+                &admin_badge_arg,
+                &*self.test_environment,
+            )
+            .unwrap_or_else(|error| panic!("{}", error));
+
+        let component = self.test_environment.component(component_name);
+        self.env_bindings
+            .push((component_arg, component.address().to_string()));
+        if let Some(badge) = component.admin_badge() {
+            self.env_bindings.push((admin_badge_arg, badge.clone()));
+        }
+
+        self.method_names.push(method.name().to_string());
+
+        self
+    }
+
+    /// Writes the manifest of the transaction and returns the [`ManifestCall`] running it
+    pub fn build(mut self) -> ManifestCall<'a> {
+        if self.method_names.is_empty() {
+            panic!("Cannot build a transaction without any call")
+        }
+
+        self.manifest.finish();
+        let manifest_name = format!("transaction_{}", self.method_names.join("_"));
+        self.test_environment
+            .write_manifest(self.manifest.build(), &manifest_name);
+
+        ManifestCall::new(self.test_environment)
+            .call_manifest(&manifest_name, false)
+            .add_bindings(&mut self.env_bindings)
+    }
+}
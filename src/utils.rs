@@ -1,50 +1,130 @@
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::fs::{File, OpenOptions};
+use crate::backend::{AccountInfo, Backend};
+use crate::error::SqrtError;
+use std::fs::File;
 use std::io::Write;
-use std::path::Path;
-use std::process::Command;
-use std::{env, fs};
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fs, thread};
 
-pub fn run_command(command: &mut Command, is_transaction: bool) -> (String, String) {
-    let output = command.output().expect("Failed to run command line");
+/// Runs a command and returns its stdout and stderr, or an error if it could not be run
+///
+/// # Arguments
+/// * `command` - command to run
+/// * `is_transaction` - whether the command runs a transaction, whose output is returned even when it fails
+pub fn try_run_command(
+    command: &mut Command,
+    is_transaction: bool,
+) -> Result<(String, String), SqrtError> {
+    let output = command.output().map_err(|error| SqrtError::ResimFailure {
+        stdout: String::new(),
+        stderr: format!("Failed to run command line: {}", error),
+    })?;
     let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
     let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
 
     if !is_transaction && !output.status.success() {
-        println!("stdout:\n{}", stdout);
-        panic!("{}", stderr);
+        Err(SqrtError::ResimFailure { stdout, stderr })
     } else {
-        (stdout, stderr)
+        Ok((stdout, stderr))
     }
 }
 
-pub fn write_manifest(output: String, path: &str, filename: &str) -> String {
-    let current_dir = env::current_dir().expect("Could not find current directory");
-    let path = format!(
-        "{}/{}/rtm/generated/{}{}",
-        current_dir.display(),
+/// Writes a generated manifest in the given directory and returns its path
+///
+/// # Arguments
+/// * `output` - content of the manifest
+/// * `directory` - directory of the generated manifests
+/// * `filename` - name of the manifest, without extension
+pub fn write_manifest(output: String, directory: &Path, filename: &str) -> String {
+    let path = generated_manifest_path(directory, filename);
+
+    // Manifests are generated before every call, so the file is only rewritten when its content changed
+    if fs::read_to_string(&path).is_ok_and(|current| current == output) {
+        return path;
+    }
+    write_file(&path, &output);
+
+    path
+}
+
+/// Writes a file through a temporary file, so that tests running in parallel never read a partially written file
+///
+/// # Arguments
+/// * `path` - path of the file
+/// * `output` - content of the file
+pub fn write_file(path: &str, output: &str) {
+    let tmp_path = format!(
+        "{}.{}.{:?}.tmp",
         path,
-        filename,
-        ".rtm"
+        process::id(),
+        thread::current().id()
     );
-    if !Path::new(&path).exists() {
-        File::create(path.clone()).expect("Could not create a new file");
-    }
-    let mut file = match OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(path.clone())
-    {
+    let mut file = match File::create(&tmp_path) {
         Ok(f) => f,
         Err(_) => {
-            panic!("Could not access path {}", path);
+            panic!("Could not access path {}", tmp_path);
         }
     };
     file.write_all(output.as_bytes())
-        .expect("Could not output rtm");
+        .expect("Could not write file");
+    fs::rename(&tmp_path, path).expect("Could not write file");
+}
 
-    path
+pub fn copy_dir(from: &Path, to: &Path) {
+    fs::create_dir_all(to).expect("Could not create directory");
+    for entry in fs::read_dir(from).expect("Could not read directory") {
+        let entry = entry.expect("Could not read directory");
+        let destination = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            copy_dir(&entry.path(), &destination);
+        } else {
+            fs::copy(entry.path(), destination).expect("Could not copy file");
+        }
+    }
+}
+
+/// Returns a new path in the temporary directory, different for every call
+///
+/// # Arguments
+/// * `prefix` - prefix of the name of the directory
+pub fn new_directory(prefix: &str) -> PathBuf {
+    static DIRECTORY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    env::temp_dir().join(format!(
+        "{}-{}-{}",
+        prefix,
+        process::id(),
+        DIRECTORY_COUNT.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
+/// Temporary directory, deleted when dropped
+pub struct TempDirectory {
+    path: PathBuf,
+}
+
+impl TempDirectory {
+    /// Creates a new temporary directory
+    ///
+    /// # Arguments
+    /// * `prefix` - prefix of the name of the directory
+    pub fn new(prefix: &str) -> TempDirectory {
+        let path = new_directory(prefix);
+        fs::create_dir_all(&path).expect("Could not create temporary directory");
+        TempDirectory { path }
+    }
+
+    /// Returns the path of the directory
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDirectory {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
 }
 
 pub fn create_dir(path: &str) {
@@ -58,69 +138,64 @@ pub fn create_dir(path: &str) {
         }
     }
     let custom_path = format!("{}{}", path_string, "rtm/custom");
-    let generated_path = format!("{}{}", path_string, "rtm/generated");
     fs::create_dir_all(&custom_path)
         .expect("Something went wrong when trying to create custom rtm folder path");
-    fs::create_dir_all(&generated_path)
-        .expect("Something went wrong when trying to create generated rtm folder path");
+}
+
+/// Returns the content of a manifest, or an error if it could not be read
+///
+/// # Arguments
+/// * `path` - path of the manifest
+pub fn read_manifest(path: &str) -> Result<String, SqrtError> {
+    fs::read_to_string(path).map_err(|error| SqrtError::ResimFailure {
+        stdout: String::new(),
+        stderr: format!("Could not read manifest {}: {}", path, error),
+    })
 }
 
 pub fn run_manifest(
-    package_path: &str,
-    name: &str,
-    custom_manifest: bool,
+    backend: &mut dyn Backend,
+    path: &str,
     env_variables_binding: Vec<(String, String)>,
-) -> (String, String, String) {
-    let current_dir = env::current_dir().expect("Could not find current directory");
-    let sub_folder = if custom_manifest {
-        "custom"
-    } else {
-        "generated"
-    };
-    let path = format!(
-        "{}/{}/rtm/{}/{}{}",
-        current_dir.display(),
-        package_path,
-        sub_folder,
-        name,
-        ".rtm"
-    );
-    let manifest_output =
-        manifest_called(package_path, name, custom_manifest, &env_variables_binding);
-
-    let (stdout, stderr) = run_command(
-        Command::new("resim")
-            .arg("run")
-            .arg(path)
-            .envs(env_variables_binding),
-        true,
-    );
+    signers: &[AccountInfo],
+) -> Result<(String, String, String), SqrtError> {
+    let manifest_output = read_manifest(path)?;
+    let manifest_output = bind_variables(manifest_output, &env_variables_binding);
 
-    (manifest_output, stdout, stderr)
+    let (stdout, stderr) = backend.run_manifest(path, &env_variables_binding, signers)?;
+
+    Ok((manifest_output, stdout, stderr))
 }
 
-fn manifest_called(
-    package_path: &str,
-    name: &str,
-    custom_manifest: bool,
-    env_variables_binding: &Vec<(String, String)>,
-) -> String {
+/// Returns the path of a custom manifest of a package
+///
+/// # Arguments
+/// * `package_path` - path of the package
+/// * `name` - name of the manifest, without extension
+pub fn custom_manifest_path(package_path: &str, name: &str) -> String {
     let current_dir = env::current_dir().expect("Could not find current directory");
-    let sub_folder = if custom_manifest {
-        "custom"
-    } else {
-        "generated"
-    };
-    let path = format!(
-        "{}/{}/rtm/{}/{}{}",
+    format!(
+        "{}/{}/rtm/custom/{}{}",
         current_dir.display(),
         package_path,
-        sub_folder,
         name,
         ".rtm"
-    );
+    )
+}
 
-    let mut manifest = fs::read_to_string(path).expect("Should have been able to read the file");
+/// Returns the path of a generated manifest
+///
+/// # Arguments
+/// * `directory` - directory of the generated manifests
+/// * `name` - name of the manifest, without extension
+pub fn generated_manifest_path(directory: &Path, name: &str) -> String {
+    directory
+        .join(format!("{}{}", name, ".rtm"))
+        .display()
+        .to_string()
+}
+
+pub fn bind_variables(mut manifest: String, env_variables_binding: &[(String, String)]) -> String {
     for (arg_name, arg_value) in env_variables_binding {
         let gen_arg = format!("${{{}}}", arg_name);
         manifest = manifest.replace(gen_arg.as_str(), arg_value.as_str());
@@ -128,26 +203,3 @@ fn manifest_called(
 
     manifest
 }
-
-pub fn generated_manifest_exists(method_name: &str, path: &str) -> bool {
-    let current_dir = env::current_dir().expect("Could not find current directory");
-    let path = format!(
-        "{}/{}/rtm/generated/{}{}",
-        current_dir.display(),
-        path,
-        method_name,
-        ".rtm"
-    );
-    Path::new(&path).exists()
-}
-
-pub fn generate_owner_badge() -> String {
-    let output = run_command(Command::new("resim").arg("new-simple-badge"), false);
-
-    lazy_static! {
-        static ref NFADDRESS_RE: Regex = Regex::new(r#"NonFungibleGlobalId: (.*)"#).unwrap();
-    }
-
-    let badge_address = &NFADDRESS_RE.captures(&output.0).expect("Unexpected error")[1];
-    String::from(badge_address)
-}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod derive_tests {
+    use scrypto::prelude::{dec, Decimal};
+    use sqrt::blueprint::{AdminBadge, Blueprint};
+    use sqrt::method::{Arg, Method};
+
+    #[derive(Blueprint)]
+    #[blueprint(instantiate = "new", admin_badge = external("owner"))]
+    struct TestBp {}
+
+    #[derive(Blueprint)]
+    #[blueprint(name = "Other", instantiate = "instantiate_other")]
+    struct OtherBp {}
+
+    #[derive(Method)]
+    enum TestMethods {
+        GetNFT,
+        HTTPRequestSender(String),
+        Withdraw1Token,
+        Update(
+            #[arg(account, position = 2)] String,
+            u8,
+            #[arg(fungible_bucket = "radix", position = 0)] Decimal,
+        ),
+        Swap(#[arg(fungible_bucket)] (String, Decimal)),
+        #[method(name = "withdraw_fees", admin, manifest = "withdraw")]
+        Withdraw,
+    }
+
+    #[test]
+    fn test_blueprint() {
+        let blueprint = TestBp {};
+        assert_eq!(blueprint.name(), "TestBp");
+        assert_eq!(blueprint.instantiation_name(), "new");
+        assert!(
+            matches!(blueprint.has_admin_badge(), AdminBadge::External(badge) if badge == "owner")
+        );
+
+        let other = OtherBp {};
+        assert_eq!(other.name(), "Other");
+        assert_eq!(other.instantiation_name(), "instantiate_other");
+        assert!(matches!(other.has_admin_badge(), AdminBadge::None));
+    }
+
+    #[test]
+    fn test_method_names() {
+        assert_eq!(TestMethods::GetNFT.name(), "get_nft");
+        assert_eq!(
+            TestMethods::HTTPRequestSender(String::new()).name(),
+            "http_request_sender"
+        );
+        assert_eq!(TestMethods::Withdraw1Token.name(), "withdraw1_token");
+        assert_eq!(TestMethods::Withdraw.name(), "withdraw_fees");
+    }
+
+    #[test]
+    fn test_method_attributes() {
+        assert!(TestMethods::Withdraw.needs_admin_badge());
+        assert_eq!(
+            TestMethods::Withdraw.custom_manifest_name(),
+            Some("withdraw")
+        );
+        assert!(!TestMethods::GetNFT.needs_admin_badge());
+        assert_eq!(TestMethods::GetNFT.custom_manifest_name(), None);
+        assert!(TestMethods::GetNFT.args().is_none());
+    }
+
+    #[test]
+    fn test_method_args_positions() {
+        let args = TestMethods::Update(String::from("alice"), 3, dec!(10))
+            .args()
+            .unwrap();
+        assert_eq!(args.len(), 3);
+        assert!(
+            matches!(&args[0], Arg::FungibleBucketArg(resource, amount) if resource == "radix" && *amount == dec!(10))
+        );
+        assert!(matches!(args[1], Arg::U8(3)));
+        assert!(matches!(&args[2], Arg::AccountAddressArg(account) if account == "alice"));
+    }
+
+    #[test]
+    fn test_method_args_tuple() {
+        let args = TestMethods::Swap((String::from("btc"), dec!(2)))
+            .args()
+            .unwrap();
+        assert!(
+            matches!(&args[..], [Arg::FungibleBucketArg(resource, amount)] if resource == "btc" && *amount == dec!(2))
+        );
+    }
+}
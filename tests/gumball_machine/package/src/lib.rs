@@ -37,6 +37,7 @@ mod gumball_machine {
             // if the caller has sent too few, or sent something other than XRD, they'll get a runtime error
             let our_share = payment.take(self.price);
             self.collected_xrd.put(our_share);
+            info!("Bought a gumball for {} XRD", self.price);
 
             // we could have simplified the above into a single line, like so:
             // self.collected_xrd.put(payment.take(self.price));
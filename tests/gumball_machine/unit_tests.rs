@@ -2,11 +2,14 @@
 mod gumball_tests {
     use scrypto::prelude::{dec, Decimal};
     use sqrt::blueprint::{AdminBadge, Blueprint};
-    use sqrt::error::other_error;
+    use sqrt::cost_tracking::{CostTracking, OnRegression};
+    use sqrt::error::{insufficient_balance, other_error, receipt_matching, rejected, SqrtError};
+    use sqrt::fee::{FeeLock, FeePayer};
     use sqrt::method::Arg::DecimalArg;
     use sqrt::method::{Arg, Method};
     use sqrt::method_args;
     use sqrt::package::Package;
+    use sqrt::receipt::{Level, TransactionStatus};
     use sqrt::test_environment::TestEnvironment;
 
     struct GumballBp {}
@@ -88,7 +91,11 @@ mod gumball_tests {
         test_env.publish_package("gumball", gumball_package);
         test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
 
-        test_env.call_method(GumballMethods::GetPrice).run();
+        let outputs = test_env
+            .call_method(GumballMethods::GetPrice)
+            .run_with_outputs();
+        let price: Decimal = outputs[1].decode();
+        assert_eq!(price, dec!("1.5"));
     }
 
     #[test]
@@ -110,6 +117,145 @@ mod gumball_tests {
         assert!(xrd_owned_before_call - new_amount_xrd_amount > dec!("1.5"));
     }
 
+    #[test]
+    fn test_buy_gumball_worktop_assertion() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .assert_worktop_contains("gumball", Decimal::one())
+            .run();
+
+        assert_eq!(test_env.amount_owned_by_current("gumball"), Decimal::one());
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Worktop assertion 1 failed: expected at least 2 gumball on the worktop"
+    )]
+    fn test_buy_gumball_wrong_worktop_assertion() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .assert_worktop_contains("gumball", Decimal::one())
+            .assert_worktop_contains("gumball", dec!(2))
+            .run();
+    }
+
+    #[test]
+    fn test_buy_gumball_fees() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        test_env.set_fee_lock(FeeLock::new(FeePayer::Caller, dec!(10)).contingent());
+        let fees = test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .run_with_fees();
+
+        assert!(fees.cost_units_consumed > 0);
+        assert!(fees.cost_units_consumed <= fees.cost_unit_limit);
+        assert_eq!(fees.total, fees.execution_cost + fees.royalty_cost);
+        assert_eq!(test_env.amount_owned_by_current("gumball"), Decimal::one());
+    }
+
+    #[test]
+    fn test_fee_paid_by_another_account() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+        test_env.create_account("sponsor");
+        test_env.set_current_account("sponsor");
+        let sponsor_balance = test_env.amount_owned_by_current("radix");
+        test_env.set_current_account("default");
+
+        // The caller only pays for the gumball, the sponsor signs the transaction and pays its fee
+        test_env.set_fee_lock(FeeLock::new(
+            FeePayer::Account("sponsor".to_string()),
+            dec!(10),
+        ));
+        let fees = test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .expect_balance_changes(vec![("default", "radix", dec!("-1.5"))])
+            .run_with_fees();
+
+        test_env.set_current_account("sponsor");
+        test_env.update();
+        assert_eq!(
+            test_env.amount_owned_by_current("radix"),
+            sponsor_balance - fees.total
+        );
+    }
+
+    #[test]
+    fn test_buy_gumball_track_cost() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        test_env.set_cost_tracking(CostTracking::new(dec!(5), OnRegression::Fail));
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .track_cost("buy_gumball_15")
+            .run();
+
+        let costs =
+            std::fs::read_to_string("tests/gumball_machine/package/rtm/costs.json").unwrap();
+        assert!(costs.contains("\"buy_gumball_15\""));
+    }
+
+    #[test]
+    fn test_buy_gumball_logs() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .expect_log(Level::Info, "Bought a gumball")
+            .expect_log_matching(Level::Info, r"for 1\.5 XRD$")
+            .run();
+    }
+
+    #[test]
+    #[should_panic(expected = "No Warn log matching `Bought a gumball`")]
+    fn test_buy_gumball_wrong_log_level() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .expect_log(Level::Warn, "Bought a gumball")
+            .run();
+    }
+
     #[test]
     fn test_buy_gumball_not_enough() {
         let mut test_env = TestEnvironment::new();
@@ -125,4 +271,195 @@ mod gumball_tests {
             ))
             .run();
     }
+
+    #[test]
+    fn test_buy_gumball_insufficient_balance() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(1)))
+            .should_panic(insufficient_balance())
+            .run();
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(1)))
+            .should_panic(receipt_matching("a paid failure", |receipt| {
+                matches!(receipt.status, TransactionStatus::Failure(_)) && receipt.fees.is_some()
+            }))
+            .run();
+    }
+
+    #[test]
+    fn test_fee_lock_above_balance_rejected() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+        let balance = test_env.amount_owned_by_current("radix");
+
+        test_env.set_fee_lock(FeeLock::new(FeePayer::Caller, balance + dec!(1)));
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(15)))
+            .should_panic(rejected())
+            .run();
+
+        // A rejected transaction is not committed, so not even its fee is paid
+        assert_eq!(test_env.amount_owned_by_current("radix"), balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "Manifest did not fail as expected")]
+    fn test_committed_failure_not_rejected() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+        test_env
+            .call_method(GumballMethods::BuyGumball(dec!(1)))
+            .should_panic(rejected())
+            .run();
+    }
+
+    #[test]
+    fn test_try_api() {
+        let mut test_env = TestEnvironment::new();
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env.publish_package("gumball", gumball_package);
+        test_env.new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]);
+
+        assert!(matches!(
+            test_env.try_create_account("default"),
+            Err(SqrtError::NameConflict(_))
+        ));
+        assert!(matches!(
+            test_env.try_set_current_account("unknown"),
+            Err(SqrtError::UnknownAccount(_))
+        ));
+        assert!(matches!(
+            test_env.try_amount_owned_by("default", "unknown"),
+            Err(SqrtError::UnknownResource(_))
+        ));
+
+        let result = test_env
+            .call_method(GumballMethods::BuyGumball(dec!(1)))
+            .try_run();
+        match result {
+            Err(SqrtError::TransactionFailed(receipt)) => {
+                assert!(matches!(receipt.status, TransactionStatus::Failure(_)))
+            }
+            _ => panic!("The transaction should have failed"),
+        }
+        assert!(test_env
+            .try_call_method(GumballMethods::BuyGumball(dec!(15)))
+            .unwrap()
+            .try_run()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_try_publish_and_instantiate() {
+        let mut test_env = TestEnvironment::new();
+
+        // resim cannot publish a package that does not exist
+        let missing_package = Package::new("tests/gumball_machine/missing_package");
+        assert!(matches!(
+            test_env.try_publish_package("missing", missing_package),
+            Err(SqrtError::ResimFailure { .. })
+        ));
+
+        let gumball_blueprint = Box::new(GumballBp {});
+        let mut gumball_package = Package::new("tests/gumball_machine/package");
+        gumball_package.add_blueprint("gumball", gumball_blueprint);
+        test_env
+            .try_publish_package("gumball", gumball_package)
+            .unwrap();
+        assert!(matches!(
+            test_env.try_publish_package("gumball", Package::new("tests/gumball_machine/package")),
+            Err(SqrtError::NameConflict(_))
+        ));
+
+        assert!(matches!(
+            test_env.try_new_component("gumball_comp", "unknown", vec![]),
+            Err(SqrtError::UnknownBlueprint(_))
+        ));
+        assert!(matches!(
+            test_env.try_new_component(
+                "gumball_comp",
+                "gumball",
+                vec![Arg::FungibleBucketArg(String::from("unknown"), dec!(1))]
+            ),
+            Err(SqrtError::UnknownResource(_))
+        ));
+        test_env
+            .try_new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))])
+            .unwrap();
+        assert!(matches!(
+            test_env.try_new_component("gumball_comp", "gumball", vec![DecimalArg(dec!("1.5"))]),
+            Err(SqrtError::NameConflict(_))
+        ));
+
+        assert!(matches!(
+            test_env.try_call_function("unknown", "instantiate_gumball_machine", vec![]),
+            Err(SqrtError::UnknownBlueprint(_))
+        ));
+        assert!(test_env
+            .try_call_function(
+                "gumball",
+                "instantiate_gumball_machine",
+                vec![DecimalArg(dec!(2))]
+            )
+            .unwrap()
+            .try_run()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_try_without_package_or_component() {
+        let mut test_env = TestEnvironment::new();
+
+        assert!(matches!(
+            test_env.try_new_component("gumball_comp", "gumball", vec![]),
+            Err(SqrtError::NoCurrentPackage)
+        ));
+        assert!(test_env.try_call_method(GumballMethods::GetPrice).is_err());
+        assert!(matches!(
+            test_env.try_set_fee_lock(FeeLock::new(
+                FeePayer::Component("unknown".to_string()),
+                dec!(10)
+            )),
+            Err(SqrtError::UnknownComponent(_))
+        ));
+        assert!(matches!(
+            test_env.try_amount_owned_by_current("unknown"),
+            Err(SqrtError::UnknownResource(_))
+        ));
+        assert!(matches!(
+            test_env.try_get_non_fungible_ids_owned_by("unknown", "radix"),
+            Err(SqrtError::UnknownAccount(_))
+        ));
+        assert!(matches!(
+            test_env.try_transfer_to("unknown", "radix", dec!(1)),
+            Err(SqrtError::UnknownAccount(_))
+        ));
+
+        test_env.create_account("receiver");
+        let owned = test_env.amount_owned_by_current("radix");
+        assert!(matches!(
+            test_env.try_transfer_to("receiver", "radix", owned + dec!(1)),
+            Err(SqrtError::NotEnoughTokens(_, _))
+        ));
+        test_env
+            .try_transfer_to("receiver", "radix", dec!(1))
+            .unwrap();
+        assert!(test_env.amount_owned_by_current("radix") <= owned - dec!(1));
+    }
 }
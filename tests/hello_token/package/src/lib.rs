@@ -27,12 +27,40 @@ mod hello_module {
             // Instantiate a Hello component, populating its vault with our supply of 1000 HelloToken
             Self {
                 sample_vault: Vault::with_bucket(my_bucket),
-                other_vault: Vault::with_bucket(other_bucket)
+                other_vault: Vault::with_bucket(other_bucket),
             }
             .instantiate()
             .globalize()
         }
 
+        // Another function instantiating a Hello component, with a chosen supply of HelloToken
+        pub fn instantiate_with_supply(supply: Decimal) -> ComponentAddress {
+            let my_bucket: Bucket = ResourceBuilder::new_fungible()
+                .metadata("name", "HelloToken")
+                .metadata("symbol", "HT")
+                .mint_initial_supply(supply);
+
+            let other_bucket: Bucket = ResourceBuilder::new_fungible()
+                .metadata("name", "TEST")
+                .metadata("symbol", "TST")
+                .mint_initial_supply(9);
+
+            Self {
+                sample_vault: Vault::with_bucket(my_bucket),
+                other_vault: Vault::with_bucket(other_bucket),
+            }
+            .instantiate()
+            .globalize()
+        }
+
+        // This function does not create any component and simply returns new tokens
+        pub fn airdrop(amount: Decimal) -> Bucket {
+            ResourceBuilder::new_fungible()
+                .metadata("name", "Airdrop")
+                .metadata("symbol", "AIR")
+                .mint_initial_supply(amount)
+        }
+
         // This is a method, because it needs a reference to self.  Methods can only be called on components
         pub fn free_token(&mut self) -> Bucket {
             info!("My balance is: {} HelloToken. Now giving away a token!", self.sample_vault.amount());
@@ -1,11 +1,22 @@
 #[cfg(test)]
 mod hello_tests {
     use scrypto::math::Decimal;
+    use sqrt::backend::Backend;
     use sqrt::blueprint::{AdminBadge, Blueprint};
+    use sqrt::error::SqrtError;
+    #[cfg(feature = "in-process")]
+    use sqrt::in_process::InProcessBackend;
+    use sqrt::instructions::Instruction;
+    use sqrt::method::Arg::DecimalArg;
     use sqrt::method::{Arg, Method};
     use sqrt::method_args;
+    use sqrt::non_fungible::{LocalId, NonFungibleData};
     use sqrt::package::Package;
+    use sqrt::receipt::{Level, TransactionStatus};
+    use sqrt::resim::ResimBackend;
     use sqrt::test_environment::TestEnvironment;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
 
     // To define a Blueprint, we need to implement the Blueprint Trait for some object.
     // We therefore define an empty struct for which we will implement the Blueprint Trait.
@@ -98,4 +109,381 @@ mod hello_tests {
         // We check that we indeed received 1 HelloToken after having called the FreeToken function
         assert_eq!(test_env.amount_owned_by_current("HelloToken"), Decimal::ONE);
     }
+
+    #[test]
+    fn test_custom_manifest() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+        test_env.new_component("hello_comp", "hello", vec![]);
+        test_env.create_fixed_supply_token("minter", Decimal::ONE);
+        test_env.create_non_fungible_resource(
+            "Ticket",
+            vec![(LocalId::Integer(1), NonFungibleData::new(vec![], vec![]))],
+            Some("minter"),
+        );
+
+        // The variables of the manifest are written inside their values, such as NonFungibleLocalId("${ticket_id}")
+        let env_args = vec![
+            (
+                "caller_address".to_string(),
+                Arg::AccountAddressArg(test_env.get_current_account_name().to_string()),
+            ),
+            (
+                "component_address".to_string(),
+                Arg::ComponentAddressArg(
+                    test_env.get_current_component_name().unwrap().to_string(),
+                ),
+            ),
+            (
+                "ticket".to_string(),
+                Arg::ResourceAddressArg("Ticket".to_string()),
+            ),
+            (
+                "ticket_id".to_string(),
+                Arg::NonFungibleLocalId(Box::new(Arg::StringArg(LocalId::Integer(1).to_string()))),
+            ),
+        ];
+        let receipt = test_env
+            .call_custom_manifest("free_token_and_ticket", env_args)
+            .run();
+
+        assert_eq!(receipt.status, TransactionStatus::Success);
+        assert_eq!(test_env.amount_owned_by_current("HelloToken"), Decimal::ONE);
+        assert_eq!(test_env.amount_owned_by_current("Ticket"), Decimal::ONE);
+    }
+
+    #[cfg(feature = "in-process")]
+    #[test]
+    fn test_free_token_in_process() {
+        // The same flow runs on the Scrypto engine directly, without the resim binary
+        let mut test_env = TestEnvironment::with_backend(Box::new(InProcessBackend::new()));
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+        test_env.new_component("hello_comp", "hello", vec![]);
+        test_env.get_resource("HelloToken");
+
+        let receipt = test_env.call_method(HelloMethods::FreeToken).run();
+        assert_eq!(receipt.status, TransactionStatus::Success);
+        assert_eq!(test_env.amount_owned_by_current("HelloToken"), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_parallel_environments() {
+        // Every TestEnvironment has its own ledger, so several of them can run at the same time
+        let barrier = Arc::new(Barrier::new(2));
+        let handles: Vec<_> = [("alice", 100), ("bob", 200)]
+            .into_iter()
+            .map(|(name, supply)| {
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    let mut test_env = TestEnvironment::new();
+                    test_env.create_account(name);
+                    test_env.create_fixed_supply_token(name, Decimal::from(supply));
+                    test_env.set_current_account(name);
+                    test_env.create_fixed_supply_token("shared", Decimal::from(supply));
+
+                    // Both environments have created their entities before any of them is checked
+                    barrier.wait();
+                    test_env.update();
+                    assert_eq!(
+                        test_env.amount_owned_by_current("shared"),
+                        Decimal::from(supply)
+                    );
+                    test_env.set_current_account("default");
+                    assert_eq!(
+                        test_env.amount_owned_by_current(name),
+                        Decimal::from(supply)
+                    );
+                    assert_eq!(test_env.amount_owned_by_current("shared"), Decimal::zero());
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_separate_ledgers() {
+        assert_separate_ledgers(&mut ResimBackend::new(), &mut ResimBackend::new());
+    }
+
+    #[cfg(feature = "in-process")]
+    #[test]
+    fn test_separate_ledgers_in_process() {
+        assert_separate_ledgers(&mut InProcessBackend::new(), &mut InProcessBackend::new());
+    }
+
+    // A resource created on the ledger of a backend does not exist on the ledger of another one
+    fn assert_separate_ledgers(first: &mut dyn Backend, second: &mut dyn Backend) {
+        first.reset().unwrap();
+        second.reset().unwrap();
+        first.new_account().unwrap();
+        second.new_account().unwrap();
+
+        let token = first.new_token_fixed(Decimal::from(100)).unwrap();
+        let exists = |backend: &mut dyn Backend| {
+            backend
+                .resources()
+                .unwrap()
+                .iter()
+                .any(|resource| resource.address == token)
+        };
+        assert!(exists(first));
+        assert!(!exists(second));
+    }
+
+    #[test]
+    fn test_free_token_receipt() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+        test_env.new_component("hello_comp", "hello", vec![]);
+
+        // The receipt of a call tells what happened during the transaction
+        let receipt = test_env.call_method(HelloMethods::FreeToken).run();
+        assert_eq!(receipt.status, TransactionStatus::Success);
+        assert_eq!(receipt.logs.len(), 1);
+        assert_eq!(receipt.logs[0].level, Level::Info);
+        assert!(receipt.new_entities.components.is_empty());
+        let hello_token = test_env.get_resource("HelloToken").clone();
+        assert_eq!(receipt.balance_change(&hello_token), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_instantiate_with() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+
+        // Components can also be instantiated with any other function of the blueprint
+        test_env.new_component_with(
+            "hello_comp",
+            "hello",
+            "instantiate_with_supply",
+            vec![DecimalArg(Decimal::from(5))],
+        );
+
+        test_env.call_method(HelloMethods::FreeToken).run();
+        assert_eq!(test_env.amount_owned_by_current("HelloToken"), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_call_function() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+
+        // Functions that do not create components can be called directly
+        test_env
+            .call_function("hello", "airdrop", vec![DecimalArg(Decimal::from(10))])
+            .run();
+        assert_eq!(
+            test_env.amount_owned_by_current("Airdrop"),
+            Decimal::from(10)
+        );
+    }
+
+    // Method calling free_token through its own manifest, used to check that stale manifests are regenerated
+    struct StaleFreeToken {}
+
+    impl Method for StaleFreeToken {
+        fn name(&self) -> &str {
+            "free_token"
+        }
+
+        fn args(&self) -> Option<Vec<Arg>> {
+            method_args![]
+        }
+
+        fn needs_admin_badge(&self) -> bool {
+            false
+        }
+
+        fn custom_manifest_name(&self) -> Option<&str> {
+            Some("free_token_stale")
+        }
+    }
+
+    #[test]
+    fn test_stale_manifest() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+        test_env.new_component("hello_comp", "hello", vec![]);
+
+        // A manifest generated for an older version of the method should not be reused
+        std::fs::write(
+            test_env.manifests_dir().join("free_token_stale.rtm"),
+            "CALL_METHOD ComponentAddress(\"${component_address}\") \"free_token\" Decimal(\"1\");",
+        )
+        .unwrap();
+
+        test_env.call_method(StaleFreeToken {}).run();
+        assert_eq!(test_env.amount_owned_by_current("HelloToken"), Decimal::ONE);
+    }
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+        test_env.new_component("hello_comp", "hello", vec![]);
+        test_env.call_method(HelloMethods::FreeToken).run();
+
+        // Generated manifests can be read back into instructions that render to the same manifest
+        let manifest =
+            std::fs::read_to_string(test_env.manifests_dir().join("free_token.rtm")).unwrap();
+        let instructions = Instruction::parse_manifest(&manifest).unwrap();
+        assert_eq!(instructions.len(), 3);
+        let rendered: Vec<String> = instructions
+            .iter()
+            .map(|instruction| instruction.to_string())
+            .collect();
+        assert_eq!(rendered.join("\n\n"), manifest);
+    }
+
+    #[test]
+    fn test_create_non_fungible_resource() {
+        // Resources are created and minted without publishing any package
+        let mut test_env = TestEnvironment::new();
+
+        // Every non fungible comes with the fields of the immutable and mutable parts of its data
+        let ids_and_data = vec![
+            (
+                LocalId::Integer(1),
+                NonFungibleData::new(
+                    vec![Arg::StringArg(String::from("first"))],
+                    vec![Arg::U8(1)],
+                ),
+            ),
+            (
+                LocalId::Integer(2),
+                NonFungibleData::new(
+                    vec![Arg::StringArg(String::from("second"))],
+                    vec![Arg::U8(2)],
+                ),
+            ),
+        ];
+        test_env.create_fixed_supply_token("minter", Decimal::ONE);
+        test_env.create_non_fungible_resource("Ticket", ids_and_data, Some("minter"));
+
+        assert_eq!(test_env.amount_owned_by_current("Ticket"), Decimal::from(2));
+        let ids = test_env
+            .get_non_fungible_ids_owned_by_current("Ticket")
+            .unwrap();
+        assert!(ids.contains(&LocalId::Integer(1).to_string()));
+
+        test_env.burn("Ticket", Decimal::ONE);
+        assert_eq!(test_env.amount_owned_by_current("Ticket"), Decimal::ONE);
+
+        test_env.create_mintable_token("gold", "minter");
+        test_env.mint("gold", Decimal::from(10));
+        assert_eq!(test_env.amount_owned_by_current("gold"), Decimal::from(10));
+        assert!(test_env.get_current_package_name().is_none());
+    }
+
+    #[test]
+    fn test_try_create_non_fungible_resource_invalid_ids() {
+        let mut test_env = TestEnvironment::new();
+
+        assert!(matches!(
+            test_env.try_create_non_fungible_resource("Ticket", vec![], None),
+            Err(SqrtError::InvalidIds(_))
+        ));
+        let mixed_ids = vec![
+            (LocalId::Integer(1), NonFungibleData::new(vec![], vec![])),
+            (
+                LocalId::String(String::from("two")),
+                NonFungibleData::new(vec![], vec![]),
+            ),
+        ];
+        assert!(matches!(
+            test_env.try_create_non_fungible_resource("Ticket", mixed_ids, None),
+            Err(SqrtError::InvalidIds(_))
+        ));
+        assert!(test_env.try_get_resource("Ticket").is_err());
+    }
+
+    #[test]
+    fn test_mint_and_burn() {
+        let mut test_env = TestEnvironment::new();
+        let hello_blueprint = Box::new(HelloBp {});
+        let mut hello_package = Package::new("tests/hello_token/package/");
+        hello_package.add_blueprint("hello", hello_blueprint);
+        test_env.publish_package("hello", hello_package);
+
+        test_env.create_fixed_supply_token("minter", Decimal::ONE);
+        test_env.create_mintable_token("gold", "minter");
+
+        test_env.mint("gold", Decimal::from(100));
+        assert_eq!(test_env.amount_owned_by_current("gold"), Decimal::from(100));
+
+        test_env.burn("gold", Decimal::from(40));
+        assert_eq!(test_env.amount_owned_by_current("gold"), Decimal::from(60));
+
+        test_env.set_metadata("gold", "symbol", "GLD");
+        test_env.set_metadata("gold", "description", r#"A "precious" \ metal"#);
+        let metadata = test_env.get_metadata("gold");
+        assert_eq!(metadata.get("symbol").map(String::as_str), Some("GLD"));
+        assert_eq!(
+            metadata.get("description").map(String::as_str),
+            Some(r#"A "precious" \ metal"#)
+        );
+    }
+
+    #[test]
+    fn test_try_mint_and_burn() {
+        let mut test_env = TestEnvironment::new();
+        test_env.create_fixed_supply_token("minter", Decimal::ONE);
+        test_env.create_mintable_token("gold", "minter");
+        let ids_and_data = vec![(LocalId::Integer(1), NonFungibleData::new(vec![], vec![]))];
+        test_env.create_non_fungible_resource("Ticket", ids_and_data, None);
+
+        assert!(matches!(
+            test_env.try_mint("silver", Decimal::ONE),
+            Err(SqrtError::UnknownResource(_))
+        ));
+        assert!(matches!(
+            test_env.try_mint("Ticket", Decimal::ONE),
+            Err(SqrtError::NotFungible(_))
+        ));
+        assert!(test_env.try_mint("gold", Decimal::from(10)).is_ok());
+        assert!(matches!(
+            test_env.try_burn("gold", Decimal::from(20)),
+            Err(SqrtError::TransactionFailed(_))
+        ));
+        assert_eq!(test_env.amount_owned_by_current("gold"), Decimal::from(10));
+        assert!(matches!(
+            test_env.try_set_metadata("silver", "symbol", "SLV"),
+            Err(SqrtError::UnknownResource(_))
+        ));
+    }
+
+    #[test]
+    fn test_local_id_display() {
+        assert_eq!(LocalId::Integer(1).to_string(), "#1#");
+        assert_eq!(LocalId::String(String::from("name")).to_string(), "<name>");
+        assert_eq!(LocalId::Bytes(vec![10, 27]).to_string(), "[0a1b]");
+        assert_eq!(
+            LocalId::Uuid(0xb36f5b3f_835b_406c_980f_7788d8f13c1b).to_string(),
+            "{b36f5b3f-835b-406c-980f-7788d8f13c1b}"
+        );
+    }
 }
@@ -1,4 +1,6 @@
+pub mod derive_macros;
 pub mod gumball_machine;
 pub mod hello_token;
+pub mod manifest_parsing;
 pub mod radiswap;
 pub mod radix_name_service;
@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod round_trip_tests {
+    use scrypto::prelude::Decimal;
+    use sqrt::error::SqrtError;
+    use sqrt::instructions::Instruction;
+    use sqrt::manifest::Manifest;
+    use sqrt::manifest_value::{Content, ManifestValue};
+    use sqrt::method::{AddressBook, Arg};
+
+    /// Parses the manifest, renders the instructions back and checks that both the text and the
+    /// instructions are left unchanged
+    fn assert_round_trip(manifest: &str) -> Vec<Instruction> {
+        let instructions = Instruction::parse_manifest(manifest).unwrap();
+        let rendered: Vec<String> = instructions.iter().map(|i| i.to_string()).collect();
+        let rendered = rendered.join("\n\n");
+        assert_eq!(rendered, manifest);
+        assert_eq!(
+            Instruction::parse_manifest(&rendered),
+            Ok(instructions.clone())
+        );
+        instructions
+    }
+
+    #[test]
+    fn test_worktop_instructions() {
+        let instructions = assert_round_trip(
+            "TAKE_FROM_WORKTOP\n\
+             \tResourceAddress(\"${xrd}\")\n\
+             \tBucket(\"all\");\n\n\
+             TAKE_FROM_WORKTOP_BY_AMOUNT\n\
+             \tDecimal(\"1.5\")\n\
+             \tResourceAddress(\"${xrd}\")\n\
+             \tBucket(\"some\");\n\n\
+             TAKE_FROM_WORKTOP_BY_IDS\n\
+             \tArray<NonFungibleLocalId>(NonFungibleLocalId(\"#1#\"), NonFungibleLocalId(\"#2#\"))\n\
+             \tResourceAddress(\"${nft}\")\n\
+             \tBucket(\"ids\");\n\n\
+             RETURN_TO_WORKTOP\n\
+             \tBucket(\"some\");\n\n\
+             ASSERT_WORKTOP_CONTAINS\n\
+             \tResourceAddress(\"${xrd}\");\n\n\
+             ASSERT_WORKTOP_CONTAINS_BY_AMOUNT\n\
+             \tDecimal(\"1\")\n\
+             \tResourceAddress(\"${xrd}\");\n\n\
+             ASSERT_WORKTOP_CONTAINS_BY_IDS\n\
+             \tArray<NonFungibleLocalId>(NonFungibleLocalId(\"#1#\"))\n\
+             \tResourceAddress(\"${nft}\");",
+        );
+
+        assert_eq!(instructions.len(), 7);
+        assert!(matches!(
+            &instructions[2],
+            Instruction::TakeFromWorktopByIds { bucket, .. } if bucket == "ids"
+        ));
+        assert!(matches!(
+            &instructions[3],
+            Instruction::ReturnToWorktop { bucket } if bucket == "some"
+        ));
+    }
+
+    #[test]
+    fn test_auth_zone_and_proof_instructions() {
+        let instructions = assert_round_trip(
+            "CREATE_PROOF_FROM_AUTH_ZONE\n\
+             \tResourceAddress(\"${badge}\")\n\
+             \tProof(\"badge\");\n\n\
+             CREATE_PROOF_FROM_AUTH_ZONE_BY_AMOUNT\n\
+             \tDecimal(\"2\")\n\
+             \tResourceAddress(\"${xrd}\")\n\
+             \tProof(\"xrd\");\n\n\
+             CREATE_PROOF_FROM_AUTH_ZONE_BY_IDS\n\
+             \tArray<NonFungibleLocalId>(NonFungibleLocalId(\"#1#\"))\n\
+             \tResourceAddress(\"${nft}\")\n\
+             \tProof(\"nft\");\n\n\
+             POP_FROM_AUTH_ZONE\n\
+             \tProof(\"popped\");\n\n\
+             CLONE_PROOF\n\
+             \tProof(\"popped\")\n\
+             \tProof(\"clone\");\n\n\
+             PUSH_TO_AUTH_ZONE\n\
+             \tProof(\"clone\");\n\n\
+             CREATE_PROOF_FROM_BUCKET\n\
+             \tBucket(\"bucket\")\n\
+             \tProof(\"from_bucket\");\n\n\
+             DROP_PROOF\n\
+             \tProof(\"from_bucket\");\n\n\
+             CLEAR_AUTH_ZONE;\n\n\
+             DROP_ALL_PROOFS;",
+        );
+
+        assert_eq!(instructions.len(), 10);
+        assert!(matches!(
+            &instructions[4],
+            Instruction::CloneProof { proof, new_proof } if proof == "popped" && new_proof == "clone"
+        ));
+        assert_eq!(instructions[8], Instruction::ClearAuthZone);
+        assert_eq!(instructions[9], Instruction::DropAllProofs);
+    }
+
+    #[test]
+    fn test_resource_instructions() {
+        let instructions = assert_round_trip(
+            "MINT_FUNGIBLE\n\
+             \tResourceAddress(\"${token}\")\n\
+             \tDecimal(\"100\");\n\n\
+             MINT_NON_FUNGIBLE\n\
+             \tResourceAddress(\"${nft}\")\n\
+             \tMap<NonFungibleLocalId, Tuple>(NonFungibleLocalId(\"#1#\"), Tuple(Tuple(), Tuple()));\n\n\
+             BURN_RESOURCE\n\
+             \tBucket(\"burnt\");\n\n\
+             RECALL_RESOURCE\n\
+             \tBytes(\"00\")\n\
+             \tDecimal(\"3\");\n\n\
+             CREATE_FUNGIBLE_RESOURCE\n\
+             \t18u8\n\
+             \tMap<String, String>(\"name\", \"Token\")\n\
+             \tMap<Enum, Tuple>()\n\
+             \tNone;",
+        );
+
+        assert_eq!(instructions.len(), 5);
+        assert!(matches!(
+            &instructions[2],
+            Instruction::BurnResource { bucket } if bucket == "burnt"
+        ));
+    }
+
+    #[test]
+    fn test_publish_package_and_set_metadata() {
+        let instructions = assert_round_trip(
+            "PUBLISH_PACKAGE\n\
+             \tBlob(\"${code_hash}\")\n\
+             \tBlob(\"${abi_hash}\")\n\
+             \tMap<String, Tuple>()\n\
+             \tMap<String, String>()\n\
+             \tTuple(Map<Tuple, Enum>(), Map<String, Enum>(), Enum(\"AccessRule::DenyAll\"), Map<Tuple, Enum>(), Map<String, Enum>(), Enum(\"AccessRule::DenyAll\"));\n\n\
+             SET_METADATA\n\
+             \tResourceAddress(\"${token}\")\n\
+             \t\"description\"\n\
+             \t\"A \\\"quoted\\\" \\\\ value\";",
+        );
+
+        assert_eq!(instructions.len(), 2);
+        assert!(matches!(
+            &instructions[0],
+            Instruction::PublishPackage { .. }
+        ));
+        match &instructions[1] {
+            Instruction::SetMetadata { value, .. } => {
+                assert_eq!(
+                    value,
+                    &ManifestValue::String(Content::Literal(String::from("A \"quoted\" \\ value")))
+                );
+            }
+            other => panic!("Expected SET_METADATA, got {}", other),
+        }
+    }
+
+    #[test]
+    fn test_option_result_and_keyword_values() {
+        let instructions = assert_round_trip(
+            "CALL_METHOD\n\
+             \tComponentAddress(\"${component}\")\n\
+             \t\"update\"\n\
+             \tSome(5u8)\n\
+             \tNone\n\
+             \tOk(\"done\")\n\
+             \tErr(Decimal(\"-1\"))\n\
+             \tSome(Tuple(true, ()))\n\
+             \tExpression(\"ENTIRE_WORKTOP\");",
+        );
+
+        match &instructions[0] {
+            Instruction::CallMethod {
+                method_name, args, ..
+            } => {
+                assert_eq!(method_name, "update");
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                assert_eq!(
+                    args,
+                    vec![
+                        "Some(5u8)",
+                        "None",
+                        "Ok(\"done\")",
+                        "Err(Decimal(\"-1\"))",
+                        "Some(Tuple(true, ()))",
+                        "Expression(\"ENTIRE_WORKTOP\")"
+                    ]
+                );
+            }
+            other => panic!("Expected CALL_METHOD, got {}", other),
+        }
+    }
+
+    /// Gives every entity an address made of its name
+    struct NameAddresses;
+
+    impl AddressBook for NameAddresses {
+        fn package_address(&self, name: &str) -> Result<String, SqrtError> {
+            Ok(format!("package_{}", name))
+        }
+
+        fn component_address(&self, name: &str) -> Result<String, SqrtError> {
+            Ok(format!("component_{}", name))
+        }
+
+        fn account_address(&self, name: &str) -> Result<String, SqrtError> {
+            Ok(format!("account_{}", name))
+        }
+
+        fn resource_address(&self, name: &str) -> Result<String, SqrtError> {
+            Ok(format!("resource_{}", name))
+        }
+    }
+
+    #[test]
+    fn test_built_manifest_round_trip() {
+        let mut manifest = Manifest::new();
+        manifest
+            .call_function(
+                "Hello",
+                "instantiate",
+                &[
+                    Arg::StringArg(String::from("say \"hi\"")),
+                    Arg::DecimalArg(Decimal::from(5)),
+                    Arg::TupleArg(vec![
+                        Arg::U8(1),
+                        Arg::ResourceAddressArg(String::from("xrd")),
+                    ]),
+                    Arg::NonFungibleLocalId(Box::new(Arg::StringArg(String::from("#1#")))),
+                ],
+                &NameAddresses,
+            )
+            .unwrap();
+
+        assert_eq!(
+            Instruction::parse_manifest(&manifest.build()),
+            Ok(manifest.instructions())
+        );
+    }
+
+    #[test]
+    fn test_build_string_looking_like_a_variable() {
+        // The text of the string is kept, even if it is read back as a variable
+        let mut manifest = Manifest::new();
+        manifest
+            .call_function(
+                "Hello",
+                "greet",
+                &[Arg::StringArg(String::from("${name}"))],
+                &NameAddresses,
+            )
+            .unwrap();
+
+        assert!(manifest.build().contains("\"${name}\""));
+    }
+}
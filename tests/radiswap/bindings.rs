@@ -0,0 +1,27 @@
+// Generated by sqrt from the ABI of blueprint Radiswap
+
+#[allow(unused_imports)]
+use scrypto::prelude::{Decimal, PreciseDecimal};
+use sqrt::blueprint::Blueprint;
+#[allow(unused_imports)]
+use sqrt::method::{Arg, Method};
+
+#[derive(Blueprint)]
+#[blueprint(name = "Radiswap", instantiate = "instantiate_pool")]
+pub struct RadiswapBp {}
+
+#[derive(Method)]
+pub enum RadiswapMethods {
+    /// `add_liquidity(a_tokens: Bucket, b_tokens: Bucket)`
+    #[method(name = "add_liquidity")]
+    AddLiquidity(#[arg(fungible_bucket)] (String, Decimal), #[arg(fungible_bucket)] (String, Decimal)),
+    /// `remove_liquidity(lp_tokens: Bucket)`
+    #[method(name = "remove_liquidity")]
+    RemoveLiquidity(#[arg(fungible_bucket)] (String, Decimal)),
+    /// `swap(input_tokens: Bucket)`
+    #[method(name = "swap")]
+    Swap(#[arg(fungible_bucket)] (String, Decimal)),
+    /// `get_pair()`
+    #[method(name = "get_pair")]
+    GetPair,
+}
@@ -1,12 +1,20 @@
 #[cfg(test)]
 mod radiswap_test {
+    use lazy_static::lazy_static;
     use scrypto::prelude::{dec, Decimal};
-    use sqrt::blueprint::{AdminBadge, Blueprint};
-    use sqrt::method::Arg::{DecimalArg, FungibleBucketArg, StringArg};
+    use sqrt::abi::AbiType;
+    use sqrt::blueprint::{AdminBadge, Blueprint, ReturnedItem};
+    use sqrt::method::Arg::{DecimalArg, FungibleBucketArg, StringArg, WorktopBucketArg};
     use sqrt::method::{Arg, Method};
     use sqrt::method_args;
     use sqrt::package::Package;
-    use sqrt::test_environment::TestEnvironment;
+    use sqrt::test_environment::{Snapshot, TestEnvironment};
+
+    // Bindings generated from the ABI of the package by `BlueprintAbi::write_bindings`
+    #[allow(dead_code)]
+    mod bindings {
+        include!("bindings.rs");
+    }
 
     struct RadiSwapBp {}
 
@@ -22,20 +30,32 @@ mod radiswap_test {
         fn has_admin_badge(&self) -> AdminBadge {
             AdminBadge::None
         }
+
+        fn instantiation_return(&self) -> Vec<ReturnedItem> {
+            vec![
+                ReturnedItem::Component,
+                ReturnedItem::Resource("lp".to_string()),
+            ]
+        }
     }
 
     enum RadiSwapMethods {
         AddLiquidity(String, Decimal, String, Decimal),
+        // Adds liquidity with all the tokens of the second resource that are on the worktop
+        AddLiquidityFromWorktop(String, Decimal, String),
         RemoveLiquidity(String, Decimal),
         Swap(String, Decimal),
+        GetPair,
     }
 
     impl Method for RadiSwapMethods {
         fn name(&self) -> &str {
             match self {
                 RadiSwapMethods::AddLiquidity(_, _, _, _) => "add_liquidity",
+                RadiSwapMethods::AddLiquidityFromWorktop(_, _, _) => "add_liquidity",
                 RadiSwapMethods::RemoveLiquidity(_, _) => "remove_liquidity",
                 RadiSwapMethods::Swap(_, _) => "swap",
+                RadiSwapMethods::GetPair => "get_pair",
             }
         }
 
@@ -47,12 +67,21 @@ mod radiswap_test {
                         FungibleBucketArg(b_name.clone(), b_amount.clone())
                     ]
                 }
+                RadiSwapMethods::AddLiquidityFromWorktop(a_name, a_amount, b_name) => {
+                    method_args![
+                        FungibleBucketArg(a_name.clone(), a_amount.clone()),
+                        WorktopBucketArg(b_name.clone())
+                    ]
+                }
                 RadiSwapMethods::RemoveLiquidity(lp_token_name, amount) => {
                     method_args![FungibleBucketArg(lp_token_name.clone(), amount.clone())]
                 }
                 RadiSwapMethods::Swap(input_tokens_name, amount) => {
                     method_args![FungibleBucketArg(input_tokens_name.clone(), amount.clone())]
                 }
+                RadiSwapMethods::GetPair => {
+                    method_args![]
+                }
             }
         }
 
@@ -65,23 +94,31 @@ mod radiswap_test {
         }
     }
 
-    #[test]
-    fn test_publish() {
+    // Publishes the Radiswap package in a new TestEnvironment
+    fn publish_radiswap() -> TestEnvironment {
         let mut test_env = TestEnvironment::new();
         let radiswap_blueprint = Box::new(RadiSwapBp {});
         let mut radiswap_package = Package::new("tests/radiswap/package/");
         radiswap_package.add_blueprint("radiswap_bp", radiswap_blueprint);
         test_env.publish_package("radiswap_pkg", radiswap_package);
+        test_env
     }
 
-    #[test]
-    fn test_instantiate() {
+    lazy_static! {
+        // The pool is instantiated once, every test restoring its own environment to it
+        static ref RADISWAP_FIXTURE: Snapshot = instantiate_radiswap().snapshot();
+    }
+
+    // Returns a new TestEnvironment restored to the instantiated pool
+    fn radiswap_fixture() -> TestEnvironment {
         let mut test_env = TestEnvironment::new();
-        let radiswap_blueprint = Box::new(RadiSwapBp {});
-        let mut radiswap_package = Package::new("tests/radiswap/package/");
-        radiswap_package.add_blueprint("radiswap_bp", radiswap_blueprint);
-        test_env.publish_package("radiswap_pkg", radiswap_package);
+        test_env.restore(&RADISWAP_FIXTURE);
+        test_env
+    }
 
+    // Instantiates a pool from 1000 usd and 100 btc, the default account keeping the rest of the tokens
+    fn instantiate_radiswap() -> TestEnvironment {
+        let mut test_env = publish_radiswap();
         test_env.create_fixed_supply_token("usd", dec!(100000));
         test_env.create_fixed_supply_token("btc", dec!(100000));
         let args = vec![
@@ -94,7 +131,17 @@ mod radiswap_test {
             DecimalArg(dec!("0.003")),
         ];
         test_env.new_component("lp_comp", "radiswap_bp", args);
+        test_env
+    }
+
+    #[test]
+    fn test_publish() {
+        publish_radiswap();
+    }
 
+    #[test]
+    fn test_instantiate() {
+        let test_env = radiswap_fixture();
         let lp_tokens_owned = test_env.amount_owned_by_current("usd-btc lp");
         let usd_owned = test_env.amount_owned_by_current("usd");
         let btc_owned = test_env.amount_owned_by_current("btc");
@@ -105,26 +152,53 @@ mod radiswap_test {
     }
 
     #[test]
-    fn test_add_liquidity() {
-        let mut test_env = TestEnvironment::new();
-        let radiswap_blueprint = Box::new(RadiSwapBp {});
-        let mut radiswap_package = Package::new("tests/radiswap/package/");
-        radiswap_package.add_blueprint("radiswap_bp", radiswap_blueprint);
-        test_env.publish_package("radiswap_pkg", radiswap_package);
+    fn test_instantiation_return() {
+        let test_env = radiswap_fixture();
+        assert_eq!(
+            test_env.get_resource("lp"),
+            test_env.get_resource("usd-btc lp")
+        );
+        assert_eq!(test_env.amount_owned_by_current("lp"), dec!(1));
+    }
 
-        test_env.create_fixed_supply_token("usd", dec!(100000));
-        test_env.create_fixed_supply_token("btc", dec!(100000));
-        let args = vec![
-            FungibleBucketArg("usd".to_string(), dec!(1000)),
-            FungibleBucketArg("btc".to_string(), dec!(100)),
-            DecimalArg(dec!(1)),
-            StringArg("LP".to_string()),
-            StringArg("USD-BTC LP".to_string()),
-            StringArg("".to_string()),
-            DecimalArg(dec!("0.003")),
-        ];
-        test_env.new_component("lp_comp", "radiswap_bp", args);
+    #[test]
+    fn test_abi_bindings() {
+        let mut test_env = publish_radiswap();
+        let abi = test_env.export_abi("radiswap_pkg", "Radiswap");
+        let swap = abi.function("swap").unwrap();
+        assert!(swap.is_method);
+        assert_eq!(
+            swap.inputs,
+            vec![("input_tokens".to_string(), AbiType::Bucket)]
+        );
+        assert!(!abi.function("instantiate_pool").unwrap().is_method);
 
+        // The checked in bindings should match the current version of the package
+        let bindings = abi.generate_bindings("instantiate_pool");
+        assert_eq!(bindings, include_str!("bindings.rs"));
+    }
+
+    #[test]
+    fn test_generated_bindings() {
+        let mut test_env = radiswap_fixture();
+        test_env
+            .call_method(bindings::RadiswapMethods::Swap((
+                "usd".to_string(),
+                dec!(1000),
+            )))
+            .run();
+        assert_eq!(test_env.amount_owned_by_current("usd"), dec!(98000));
+
+        let outputs = test_env
+            .call_method(bindings::RadiswapMethods::GetPair)
+            .run_with_outputs();
+        let (a_address, _): (String, String) = outputs[1].decode();
+        assert_eq!(&a_address, test_env.get_resource("usd"));
+    }
+
+    #[test]
+    fn test_add_liquidity() {
+        let mut test_env = radiswap_fixture();
         test_env
             .call_method(RadiSwapMethods::AddLiquidity(
                 "usd".to_string(),
@@ -145,25 +219,7 @@ mod radiswap_test {
 
     #[test]
     fn test_remove_liquidity() {
-        let mut test_env = TestEnvironment::new();
-        let radiswap_blueprint = Box::new(RadiSwapBp {});
-        let mut radiswap_package = Package::new("tests/radiswap/package/");
-        radiswap_package.add_blueprint("radiswap_bp", radiswap_blueprint);
-        test_env.publish_package("radiswap_pkg", radiswap_package);
-
-        test_env.create_fixed_supply_token("usd", dec!(100000));
-        test_env.create_fixed_supply_token("btc", dec!(100000));
-        let args = vec![
-            FungibleBucketArg("usd".to_string(), dec!(1000)),
-            FungibleBucketArg("btc".to_string(), dec!(100)),
-            DecimalArg(dec!(1)),
-            StringArg("LP".to_string()),
-            StringArg("USD-BTC LP".to_string()),
-            StringArg("".to_string()),
-            DecimalArg(dec!("0.003")),
-        ];
-        test_env.new_component("lp_comp", "radiswap_bp", args);
-
+        let mut test_env = radiswap_fixture();
         test_env
             .call_method(RadiSwapMethods::RemoveLiquidity(
                 "usd-btc lp".to_string(),
@@ -182,25 +238,7 @@ mod radiswap_test {
 
     #[test]
     fn test_swap() {
-        let mut test_env = TestEnvironment::new();
-        let radiswap_blueprint = Box::new(RadiSwapBp {});
-        let mut radiswap_package = Package::new("tests/radiswap/package/");
-        radiswap_package.add_blueprint("radiswap_bp", radiswap_blueprint);
-        test_env.publish_package("radiswap_pkg", radiswap_package);
-
-        test_env.create_fixed_supply_token("usd", dec!(100000));
-        test_env.create_fixed_supply_token("btc", dec!(100000));
-        let args = vec![
-            FungibleBucketArg("usd".to_string(), dec!(1000)),
-            FungibleBucketArg("btc".to_string(), dec!(100)),
-            DecimalArg(dec!(1)),
-            StringArg("LP".to_string()),
-            StringArg("USD-BTC LP".to_string()),
-            StringArg("".to_string()),
-            DecimalArg(dec!("0.003")),
-        ];
-        test_env.new_component("lp_comp", "radiswap_bp", args);
-
+        let mut test_env = radiswap_fixture();
         test_env
             .call_method(RadiSwapMethods::Swap("usd".to_string(), dec!(1000)))
             .run();
@@ -211,4 +249,108 @@ mod radiswap_test {
         assert_eq!(usd_owned, dec!(98000));
         assert_eq!(btc_owned, dec!("99949.924887330996494743"));
     }
+
+    #[test]
+    fn test_swap_then_add_liquidity() {
+        let mut test_env = radiswap_fixture();
+        // The btc returned by the swap are used to add liquidity in the same transaction
+        test_env
+            .transaction()
+            .call_method(
+                "lp_comp",
+                RadiSwapMethods::Swap("usd".to_string(), dec!(1000)),
+            )
+            .call_method(
+                "lp_comp",
+                RadiSwapMethods::AddLiquidityFromWorktop(
+                    "usd".to_string(),
+                    dec!(1000),
+                    "btc".to_string(),
+                ),
+            )
+            .build()
+            .run();
+
+        assert_eq!(test_env.amount_owned_by_current("usd"), dec!(97000));
+        assert_eq!(test_env.amount_owned_by_current("lp"), dec!("1.5"));
+    }
+
+    #[test]
+    fn test_component_state() {
+        let mut test_env = radiswap_fixture();
+        test_env
+            .call_method(RadiSwapMethods::Swap("usd".to_string(), dec!(1000)))
+            .run();
+
+        let state = test_env.component_state("lp_comp");
+        let lp_address: String = state.field(0);
+        let fee: Decimal = state.field(4);
+
+        assert_eq!(&lp_address, test_env.get_resource("lp"));
+        assert_eq!(fee, dec!("0.003"));
+        assert_eq!(state.amount(test_env.get_resource("usd")), dec!(2000));
+        assert_eq!(
+            state.amount(test_env.get_resource("btc")),
+            dec!("50.075112669003505257")
+        );
+    }
+
+    #[test]
+    fn test_swap_balance_changes() {
+        let mut test_env = radiswap_fixture();
+        test_env
+            .call_method(RadiSwapMethods::Swap("usd".to_string(), dec!(1000)))
+            .expect_balance_changes(vec![
+                ("default", "usd", dec!(-1000)),
+                ("default", "btc", dec!("49.924887330996494743")),
+                ("default", "lp", dec!(0)),
+            ])
+            .run();
+    }
+
+    #[test]
+    #[should_panic(expected = "Unexpected balance changes")]
+    fn test_swap_wrong_balance_changes() {
+        let mut test_env = radiswap_fixture();
+        test_env
+            .call_method(RadiSwapMethods::Swap("usd".to_string(), dec!(1000)))
+            .expect_balance_changes(vec![("default", "btc", dec!(50))])
+            .run();
+    }
+
+    #[test]
+    fn test_get_pair() {
+        let mut test_env = radiswap_fixture();
+        let outputs = test_env
+            .call_method(RadiSwapMethods::GetPair)
+            .run_with_outputs();
+        let (a_address, b_address): (String, String) = outputs[1].decode();
+
+        assert_eq!(&a_address, test_env.get_resource("usd"));
+        assert_eq!(&b_address, test_env.get_resource("btc"));
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut test_env = radiswap_fixture();
+        let snapshot = test_env.snapshot();
+
+        test_env
+            .call_method(RadiSwapMethods::Swap("usd".to_string(), dec!(1000)))
+            .run();
+        assert_eq!(test_env.amount_owned_by_current("usd"), dec!(98000));
+
+        test_env.restore(&snapshot);
+        assert_eq!(test_env.amount_owned_by_current("usd"), dec!(99000));
+        assert_eq!(test_env.amount_owned_by_current("btc"), dec!(99900));
+
+        test_env
+            .call_method(RadiSwapMethods::Swap("usd".to_string(), dec!(1000)))
+            .run();
+        assert_eq!(test_env.amount_owned_by_current("usd"), dec!(98000));
+        assert_eq!(
+            test_env.amount_owned_by_current("btc"),
+            dec!("99949.924887330996494743")
+        );
+    }
 }
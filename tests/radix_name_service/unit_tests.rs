@@ -2,7 +2,7 @@
 mod rns_tests {
     use scrypto::prelude::{dec, Decimal};
     use sqrt::blueprint::{AdminBadge, Blueprint};
-    use sqrt::error::assert_fail;
+    use sqrt::error::{assert_fail, auth_failure, panic_containing, SqrtError};
     use sqrt::method::Arg::{
         AccountAddressArg, DecimalArg, FungibleBucketArg, NonFungibleBucketArg,
         NonFungibleProofArg, StringArg, U8,
@@ -75,10 +75,7 @@ mod rns_tests {
         }
 
         fn needs_admin_badge(&self) -> bool {
-            match self {
-                RNSMethods::WithdrawFees => true,
-                _ => false,
-            }
+            matches!(self, RNSMethods::WithdrawFees)
         }
 
         fn custom_manifest_name(&self) -> Option<&str> {
@@ -86,6 +83,52 @@ mod rns_tests {
         }
     }
 
+    // Same blueprint and methods, with the traits derived instead of implemented by hand
+    #[derive(Blueprint)]
+    #[blueprint(
+        name = "RadixNameService",
+        instantiate = "instantiate_rns",
+        admin_badge = internal
+    )]
+    struct DerivedRNSBp {}
+
+    #[derive(Method)]
+    enum DerivedRNSMethods {
+        RegisterName(
+            String,
+            #[arg(account)] String,
+            u8,
+            #[arg(fungible_bucket = "radix")] Decimal,
+        ),
+        UnregisterName(#[arg(non_fungible_bucket = "DomainName")] String),
+        UpdateAddress(
+            #[arg(account, position = 1)] String,
+            #[arg(non_fungible_proof = "DomainName", position = 0)] String,
+            #[arg(fungible_bucket = "radix")] Decimal,
+        ),
+        #[method(admin)]
+        WithdrawFees,
+    }
+
+    // Methods whose arguments do not match the ABI of the blueprint, used to check that they are rejected
+    #[derive(Method)]
+    enum WrongRNSMethods {
+        #[method(name = "register_name")]
+        RegisterName(
+            String,
+            #[arg(account)] String,
+            Decimal,
+            #[arg(fungible_bucket = "radix")] Decimal,
+        ),
+    }
+
+    // Admin method called without presenting the admin badge, used to check that the call is not authorized
+    #[derive(Method)]
+    enum UnauthorizedRNSMethods {
+        #[method(name = "withdraw_fees")]
+        WithdrawFees,
+    }
+
     #[test]
     fn test_publish() {
         let mut test_env = TestEnvironment::new();
@@ -276,4 +319,145 @@ mod rns_tests {
             .should_panic(assert_fail("No such resource in account"))
             .run();
     }
+
+    #[test]
+    fn test_derived_traits() {
+        let mut test_env = TestEnvironment::new();
+        let rns_blueprint = Box::new(DerivedRNSBp {});
+        let mut rns_package = Package::new("tests/radix_name_service/package/");
+        rns_package.add_blueprint("rns", rns_blueprint);
+        test_env.publish_package("rns", rns_package);
+        let args = vec![
+            DecimalArg(dec!("1")),
+            DecimalArg(dec!("0.01")),
+            DecimalArg(dec!("0.01")),
+        ];
+        test_env.new_component("rns_comp", "rns", args);
+
+        test_env
+            .call_method(DerivedRNSMethods::RegisterName(
+                String::from("test.xrd"),
+                String::from("default"),
+                1,
+                dec!("15"),
+            ))
+            .run();
+        assert_eq!(
+            test_env.amount_owned_by_current("DomainName"),
+            Decimal::one()
+        );
+
+        test_env.create_account("test");
+        let id = test_env
+            .get_non_fungible_ids_owned_by_current("DomainName")
+            .unwrap()[0]
+            .clone();
+        test_env
+            .call_method(DerivedRNSMethods::UpdateAddress(
+                String::from("test"),
+                id.clone(),
+                dec!(15),
+            ))
+            .run();
+        test_env.call_method(DerivedRNSMethods::WithdrawFees).run();
+
+        test_env
+            .call_method(DerivedRNSMethods::UnregisterName(id))
+            .run();
+        assert_eq!(
+            test_env.amount_owned_by_current("DomainName"),
+            Decimal::zero()
+        );
+    }
+
+    #[test]
+    fn test_withdraw_fees_without_badge() {
+        let mut test_env = TestEnvironment::new();
+        let rns_blueprint = Box::new(RNSBp {});
+        let mut rns_package = Package::new("tests/radix_name_service/package/");
+        rns_package.add_blueprint("rns", rns_blueprint);
+        test_env.publish_package("rns", rns_package);
+        let args = vec![
+            DecimalArg(dec!("1")),
+            DecimalArg(dec!("0.01")),
+            DecimalArg(dec!("0.01")),
+        ];
+        test_env.new_component("rns_comp", "rns", args);
+
+        test_env
+            .call_method(UnauthorizedRNSMethods::WithdrawFees)
+            .should_panic(auth_failure())
+            .run();
+    }
+
+    #[test]
+    fn test_register_wrong_name() {
+        let mut test_env = TestEnvironment::new();
+        let rns_blueprint = Box::new(RNSBp {});
+        let mut rns_package = Package::new("tests/radix_name_service/package/");
+        rns_package.add_blueprint("rns", rns_blueprint);
+        test_env.publish_package("rns", rns_package);
+        let args = vec![
+            DecimalArg(dec!("1")),
+            DecimalArg(dec!("0.01")),
+            DecimalArg(dec!("0.01")),
+        ];
+        test_env.new_component("rns_comp", "rns", args);
+
+        test_env
+            .call_method(RNSMethods::RegisterName(
+                String::from("test.com"),
+                String::from("default"),
+                1,
+                dec!("15"),
+            ))
+            .should_panic(panic_containing("must end on '.xrd'"))
+            .run();
+    }
+
+    #[test]
+    #[should_panic(expected = "argument 2 of register_name expected u8, got Decimal")]
+    fn test_wrong_args() {
+        let mut test_env = TestEnvironment::new();
+        let rns_blueprint = Box::new(RNSBp {});
+        let mut rns_package = Package::new("tests/radix_name_service/package/");
+        rns_package.add_blueprint("rns", rns_blueprint);
+        test_env.publish_package("rns", rns_package);
+        let args = vec![
+            DecimalArg(dec!("1")),
+            DecimalArg(dec!("0.01")),
+            DecimalArg(dec!("0.01")),
+        ];
+        test_env.new_component("rns_comp", "rns", args);
+
+        test_env.call_method(WrongRNSMethods::RegisterName(
+            String::from("test.xrd"),
+            String::from("default"),
+            dec!("1"),
+            dec!("15"),
+        ));
+    }
+
+    #[test]
+    fn test_try_wrong_args() {
+        let mut test_env = TestEnvironment::new();
+        let rns_blueprint = Box::new(RNSBp {});
+        let mut rns_package = Package::new("tests/radix_name_service/package/");
+        rns_package.add_blueprint("rns", rns_blueprint);
+        test_env.publish_package("rns", rns_package);
+        let args = vec![
+            DecimalArg(dec!("1")),
+            DecimalArg(dec!("0.01")),
+            DecimalArg(dec!("0.01")),
+        ];
+        test_env.new_component("rns_comp", "rns", args);
+
+        let result = test_env.try_call_method(WrongRNSMethods::RegisterName(
+            String::from("test.xrd"),
+            String::from("default"),
+            dec!("1"),
+            dec!("15"),
+        ));
+        assert!(matches!(result, Err(SqrtError::ArgumentMismatch(_))));
+    }
 }